@@ -5,6 +5,22 @@ use regex::Regex;
 
 use crate::token::{Tok, Token};
 
+// Quoting settings from a <parser_descriptor>.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuoteSettings {
+    pub string_quote: Option<char>, // What the quote character is, out of ', ", $
+    pub spaces_in_quotes: bool,     // If quoted strings can contain spaces.
+}
+
+impl Default for QuoteSettings {
+    fn default() -> Self {
+        // Default spaces_in_quotes to true if no directive found - most tools
+        // do this even though it's technically against the spec.
+        Self { string_quote: None, spaces_in_quotes: true }
+    }
+}
+
 #[must_use]
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Lexer {
@@ -18,6 +34,12 @@ pub struct Lexer {
 
 impl Lexer {
     pub fn new(data: &str) -> Result<Self> {
+        Self::with_quotes(data, QuoteSettings::default())
+    }
+
+    // Like |new|, but uses |defaults| for any setting that |data| does not set
+    // itself, e.g. for a file referenced from a design that sets them.
+    pub fn with_quotes(data: &str, defaults: QuoteSettings) -> Result<Self> {
         let string_quote_rx = Regex::new(r"(?is)\(\s*string_quote\s+(.)\s*\)")?;
         let spaces_in_quotes_rx = Regex::new(r"(?is)\(\s*space_in_quoted_tokens\s+off\s*\)")?;
 
@@ -28,11 +50,9 @@ impl Lexer {
                 x => return Err(eyre!("unknown string quote character {}", x)),
             }
         } else {
-            None
+            defaults.string_quote
         };
-        // Default spaces_in_quotes to true if no directive found - most tools
-        // do this even though it's technically against the spec.
-        let spaces_in_quotes = !spaces_in_quotes_rx.is_match(data);
+        let spaces_in_quotes = defaults.spaces_in_quotes && !spaces_in_quotes_rx.is_match(data);
 
         // Remove these directives. At least the string quote needs to
         // be removed for proper lexing.
@@ -49,6 +69,10 @@ impl Lexer {
         })
     }
 
+    pub fn quotes(&self) -> QuoteSettings {
+        QuoteSettings { string_quote: self.string_quote, spaces_in_quotes: self.spaces_in_quotes }
+    }
+
     pub fn lex(mut self) -> Result<Vec<Token>> {
         while self.idx < self.data.len() {
             let c = self.next()?;
@@ -197,6 +221,17 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn with_quotes_uses_defaults() -> Result<()> {
+        let quotes = Lexer::new(r#"(parser (string_quote "))"#)?.quotes();
+        assert_eq!(quotes.string_quote, Some('"'));
+        let tokens = Lexer::with_quotes(r#"(image "R 0805")"#, quotes)?.lex()?;
+        assert_eq!(tokens[2].s, "R 0805");
+        let tokens = Lexer::with_quotes(r#"(string_quote $) (image "R")"#, quotes)?.lex()?;
+        assert_eq!(tokens[2].s, "\"R\"");
+        Ok(())
+    }
+
     #[test]
     fn string_quote_single() -> Result<()> {
         let data = "(string_quote ') (net 'test name')";
@@ -37,6 +37,7 @@
 )]
 
 pub mod lexer;
+pub mod loader;
 pub mod parser;
 pub mod token;
 pub mod types;
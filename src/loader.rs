@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use eyre::{Result, WrapErr, eyre};
+
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::types::DsnPcb;
+
+// Reads the contents of design files. Lets callers control where files
+// referenced by <file_descriptor> come from. Paths are passed as given in the
+// design, joined onto the main file's directory, so they may be absolute or
+// contain "..". Resolvers that must stay within a directory need to check this.
+pub trait FileResolver {
+    fn read(&self, path: &Path) -> Result<String>;
+}
+
+// Reads files from the filesystem.
+#[must_use]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FsResolver;
+
+impl FileResolver for FsResolver {
+    fn read(&self, path: &Path) -> Result<String> {
+        std::fs::read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))
+    }
+}
+
+// Serves files from memory, keyed by path. Useful for tests.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemResolver {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemResolver {
+    pub fn insert(&mut self, path: impl Into<PathBuf>, data: impl Into<String>) {
+        self.files.insert(path.into(), data.into());
+    }
+}
+
+impl FileResolver for MemResolver {
+    fn read(&self, path: &Path) -> Result<String> {
+        self.files.get(path).cloned().ok_or_else(|| eyre!("no such file {}", path.display()))
+    }
+}
+
+// Loads the design at |path| from the filesystem. See |load_dsn_with|.
+pub fn load_dsn(path: impl AsRef<Path>) -> Result<DsnPcb> {
+    load_dsn_with(path, &FsResolver)
+}
+
+// Loads the design at |path| and resolves any <file_descriptor> sections.
+// Referenced files are relative to the directory containing |path| and are
+// lexed with its quoting settings. Each one holds a single section, which must
+// not also be given inline or by another file.
+pub fn load_dsn_with(
+    path: impl AsRef<Path>,
+    resolver: &(impl FileResolver + ?Sized),
+) -> Result<DsnPcb> {
+    let path = path.as_ref();
+    let data = resolver.read(path)?;
+    let lexer = Lexer::new(&data)?;
+    let quotes = lexer.quotes();
+    let mut pcb = Parser::new(&lexer.lex()?).parse()?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for file in std::mem::take(&mut pcb.files) {
+        let file_path = dir.join(&file);
+        let data = resolver.read(&file_path)?;
+        pcb = Parser::new(&Lexer::with_quotes(&data, quotes)?.lex()?)
+            .parse_section(pcb)
+            .wrap_err_with(|| format!("failed to parse {}", file_path.display()))?;
+    }
+    Ok(pcb)
+}
@@ -1,3 +1,4 @@
+use std::collections::hash_map::Entry;
 use std::str::FromStr;
 
 use eyre::{Result, eyre};
@@ -6,11 +7,21 @@ use memegeom::primitive::{Rt, pt};
 
 use crate::token::{Tok, Token};
 use crate::types::{
-    DsnCircle, DsnCircuit, DsnClass, DsnClearance, DsnClearanceType, DsnComponent,
-    DsnDimensionUnit, DsnImage, DsnKeepout, DsnKeepoutType, DsnLayer, DsnLayerType, DsnLibrary,
-    DsnLockType, DsnNet, DsnNetwork, DsnPadstack, DsnPadstackShape, DsnPath, DsnPcb, DsnPin,
-    DsnPinRef, DsnPlacement, DsnPlacementRef, DsnPlane, DsnPolygon, DsnQArc, DsnRect,
-    DsnResolution, DsnRule, DsnShape, DsnSide, DsnStructure, DsnVia, DsnWindow, DsnWire, DsnWiring,
+    DsnBundle, DsnBundleGap, DsnCircle, DsnCircuit, DsnClass, DsnClassClass, DsnClearance,
+    DsnClearanceType, DsnCluster, DsnColor, DsnColors, DsnComponent, DsnCompositeNameList,
+    DsnControl, DsnCost, DsnDimensionUnit, DsnElectricalResolution, DsnElectricalUnit,
+    DsnFamilyFamily, DsnFloorPlan, DsnFromTo, DsnGrid, DsnGroup, DsnGroupSet, DsnId, DsnImage,
+    DsnImageImage, DsnInterLayerClearance, DsnJumper, DsnKeepout, DsnKeepoutType, DsnLayer,
+    DsnLayerCost, DsnLayerId, DsnLayerNoiseWeight, DsnLayerRule, DsnLayerType, DsnLibrary,
+    DsnLimit, DsnLockType, DsnLogicalPart, DsnLogicalPin, DsnMatch, DsnNet, DsnNetwork, DsnNoise,
+    DsnNumber, DsnObjectType, DsnPadstack, DsnPadstackShape, DsnPair, DsnPartLibrary,
+    DsnPartMapping, DsnPath, DsnPcb, DsnPin, DsnPinArray, DsnPinRef, DsnPlaceRule, DsnPlacement,
+    DsnPlacementRef, DsnPlane, DsnPolygon, DsnProperty, DsnQArc, DsnRect, DsnRegion, DsnRelative,
+    DsnResolution, DsnRoom, DsnRule, DsnSegment, DsnSetColor, DsnShape, DsnShield, DsnSide,
+    DsnSpacing, DsnStackViaDepth, DsnStructure, DsnSubgatePin, DsnSupplyPin, DsnTerminal,
+    DsnTestPoint, DsnTestpointRule, DsnTimeWindow, DsnTopology, DsnUseVia, DsnVia,
+    DsnViaArrayTemplate, DsnViaAtSmd, DsnViaAttr, DsnVirtualPin, DsnWindow, DsnWire, DsnWireType,
+    DsnWiring,
 };
 
 #[must_use]
@@ -31,6 +42,34 @@ impl Parser {
         Ok(self.pcb)
     }
 
+    // Parses a single section from a file referenced by a <file_descriptor>
+    // and stores it in |pcb|.
+    pub fn parse_section(mut self, pcb: DsnPcb) -> Result<DsnPcb> {
+        self.pcb = pcb;
+        let t = self.peek(1)?;
+        let present = match t.tok {
+            Tok::FloorPlan => self.pcb.floor_plan != DsnFloorPlan::default(),
+            Tok::Library => self.pcb.library != DsnLibrary::default(),
+            Tok::Network => self.pcb.network != DsnNetwork::default(),
+            Tok::PartLibrary => self.pcb.part_library != DsnPartLibrary::default(),
+            Tok::Placement => self.pcb.placement != DsnPlacement::default(),
+            Tok::Structure => self.pcb.structure != DsnStructure::default(),
+            _ => return Err(eyre!("unexpected section '{}' in file", t)),
+        };
+        if present {
+            return Err(eyre!("section '{}' is already present", t));
+        }
+        match t.tok {
+            Tok::FloorPlan => self.pcb.floor_plan = self.floor_plan()?,
+            Tok::Library => self.pcb.library = self.library()?,
+            Tok::Network => self.pcb.network = self.network()?,
+            Tok::PartLibrary => self.pcb.part_library = self.part_library()?,
+            Tok::Placement => self.pcb.placement = self.placement()?,
+            _ => self.pcb.structure = self.structure()?,
+        }
+        Ok(self.pcb)
+    }
+
     fn peek(&self, ahead: usize) -> Result<&Token> {
         if self.idx + ahead < self.toks.len() {
             Ok(&self.toks[self.idx + ahead])
@@ -84,13 +123,51 @@ impl Parser {
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
+                Tok::CapacitanceResolution => {
+                    self.pcb.capacitance_resolution =
+                        Some(self.electrical_resolution(t.tok, DsnElectricalUnit::Farad)?);
+                }
+                Tok::Colors => self.pcb.colors = Some(self.colors()?),
+                Tok::ConductanceResolution => {
+                    self.pcb.conductance_resolution =
+                        Some(self.electrical_resolution(t.tok, DsnElectricalUnit::Mho)?);
+                }
+                Tok::CurrentResolution => {
+                    self.pcb.current_resolution =
+                        Some(self.electrical_resolution(t.tok, DsnElectricalUnit::Amp)?);
+                }
+                Tok::File => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::File)?;
+                    let file = self.literal()?.to_string();
+                    self.pcb.files.push(file);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::FloorPlan => self.pcb.floor_plan = self.floor_plan()?,
+                Tok::InductanceResolution => {
+                    self.pcb.inductance_resolution =
+                        Some(self.electrical_resolution(t.tok, DsnElectricalUnit::Mhenry)?);
+                }
                 Tok::Library => self.pcb.library = self.library()?,
                 Tok::Network => self.pcb.network = self.network()?,
+                Tok::PartLibrary => self.pcb.part_library = self.part_library()?,
                 Tok::Parser => self.ignore()?, // Handled during lexing.
                 Tok::Placement => self.pcb.placement = self.placement()?,
+                Tok::ResistanceResolution => {
+                    self.pcb.resistance_resolution =
+                        Some(self.electrical_resolution(t.tok, DsnElectricalUnit::Kohm)?);
+                }
                 Tok::Resolution => self.pcb.resolution = self.resolution()?,
                 Tok::Structure => self.pcb.structure = self.structure()?,
+                Tok::TimeResolution => {
+                    self.pcb.time_resolution =
+                        Some(self.electrical_resolution(t.tok, DsnElectricalUnit::Sec)?);
+                }
                 Tok::Unit => self.pcb.unit.dimension = self.unit()?,
+                Tok::VoltageResolution => {
+                    self.pcb.voltage_resolution =
+                        Some(self.electrical_resolution(t.tok, DsnElectricalUnit::Volt)?);
+                }
                 Tok::Wiring => self.pcb.wiring = self.wiring()?,
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
@@ -99,15 +176,20 @@ impl Parser {
         Ok(())
     }
 
-    fn library(&mut self) -> Result<DsnLibrary> {
-        let mut v = DsnLibrary::default();
+    fn part_library(&mut self) -> Result<DsnPartLibrary> {
+        let mut v = DsnPartLibrary::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Library)?;
+        self.expect(Tok::PartLibrary)?;
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Image => v.images.push(self.image()?),
-                Tok::Padstack => v.padstacks.push(self.padstack()?),
+                Tok::LogicalPart => v.logical_parts.push(self.logical_part()?),
+                Tok::LogicalPartMapping => {
+                    v.logical_part_mappings.push(self.part_mapping(Tok::LogicalPartMapping)?);
+                }
+                Tok::PhysicalPartMapping => {
+                    v.physical_part_mappings.push(self.part_mapping(Tok::PhysicalPartMapping)?);
+                }
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -115,15 +197,28 @@ impl Parser {
         Ok(v)
     }
 
-    fn network(&mut self) -> Result<DsnNetwork> {
-        let mut v = DsnNetwork::default();
+    fn part_mapping(&mut self, t: Tok) -> Result<DsnPartMapping> {
+        let mut v = DsnPartMapping::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Network)?;
+        self.expect(t)?;
+        v.part_id = self.literal()?.to_string();
+        while self.peek(0)?.tok != Tok::Rparen {
+            v.component_ids.extend(self.ids(Tok::Component)?);
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn logical_part(&mut self) -> Result<DsnLogicalPart> {
+        let mut v = DsnLogicalPart::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::LogicalPart)?;
+        v.logical_part_id = self.literal()?.to_string();
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Class => v.classes.push(self.class()?),
-                Tok::Net => v.nets.push(self.net()?),
+                Tok::Pin => v.pins.extend(self.logical_pins()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -131,14 +226,49 @@ impl Parser {
         Ok(v)
     }
 
-    fn placement(&mut self) -> Result<DsnPlacement> {
-        let mut v = DsnPlacement::default();
+    fn logical_pins(&mut self) -> Result<Vec<DsnLogicalPin>> {
+        let mut v: Vec<DsnLogicalPin> = Vec::new();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Placement)?;
+        self.expect(Tok::Pin)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            if self.peek(0)?.tok == Tok::Lparen {
+                let Some(pin) = v.last_mut() else {
+                    return Err(eyre!("subgate without pin"));
+                };
+                self.expect(Tok::Lparen)?;
+                self.expect(Tok::Subgate)?;
+                pin.subgate = Some(DsnSubgatePin {
+                    subgate_id: self.literal()?.to_string(),
+                    subgate_swap_code: self.integer()?,
+                    subgate_pin_id: self.literal()?.to_string(),
+                    subgate_pin_swap_code: self.integer()?,
+                });
+                self.expect(Tok::Rparen)?;
+            } else {
+                v.push(DsnLogicalPin {
+                    pin_id: self.literal()?.to_string(),
+                    gate_id: self.literal()?.to_string(),
+                    gate_swap_code: self.integer()?,
+                    gate_pin_id: self.literal()?.to_string(),
+                    gate_pin_swap_code: self.integer()?,
+                    subgate: None,
+                });
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn floor_plan(&mut self) -> Result<DsnFloorPlan> {
+        let mut v = DsnFloorPlan::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::FloorPlan)?;
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Component => v.components.push(self.component()?),
+                Tok::Cluster => v.clusters.push(self.cluster()?),
+                Tok::Resolution => v.resolution = Some(self.resolution()?),
+                Tok::Room => v.rooms.push(self.room()?),
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -146,43 +276,73 @@ impl Parser {
         Ok(v)
     }
 
-    fn resolution(&mut self) -> Result<DsnResolution> {
-        let mut v = DsnResolution::default();
+    fn cluster(&mut self) -> Result<DsnCluster> {
+        let mut v = DsnCluster::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Resolution)?;
-        v.dimension = self.dimension()?;
-        v.amount = self.integer()?;
+        self.expect(Tok::Cluster)?;
+        v.cluster_id = self.literal()?.to_string();
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Comp => v.component_ids.extend(self.ids(Tok::Comp)?),
+                Tok::PlaceRule => v.place_rules.extend(self.place_rule()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn structure(&mut self) -> Result<DsnStructure> {
-        let mut v = DsnStructure::default();
+    fn room(&mut self) -> Result<DsnRoom> {
+        let mut v = DsnRoom::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Structure)?;
+        self.expect(Tok::Room)?;
+        v.room_id = self.literal()?.to_string();
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Boundary => {
+                Tok::Exclude => v.exclude.extend(self.ids(Tok::Exclude)?),
+                Tok::Include => v.include.extend(self.ids(Tok::Include)?),
+                Tok::PlaceRule => v.place_rules.extend(self.place_rule()?),
+                Tok::Polygon | Tok::Rect => v.shapes.push(self.shape()?),
+                Tok::Side => {
                     self.expect(Tok::Lparen)?;
-                    self.expect(Tok::Boundary)?;
-                    v.boundaries.push(self.shape()?);
+                    self.expect(Tok::Side)?;
+                    v.side = Some(self.side()?);
                     self.expect(Tok::Rparen)?;
                 }
-                Tok::Keepout | Tok::ViaKeepout | Tok::WireKeepout => {
-                    v.keepouts.push(self.keepout()?);
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn library(&mut self) -> Result<DsnLibrary> {
+        let mut v = DsnLibrary::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Library)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Directory => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Directory)?;
+                    v.directory = Some(self.literal()?.to_string());
+                    self.expect(Tok::Rparen)?;
                 }
-                Tok::Layer => v.layers.push(self.layer()?),
-                Tok::Plane => v.planes.push(self.plane()?),
-                Tok::Rule => v.rules.extend(self.rule()?),
-                Tok::Via => {
+                Tok::ExtraImageDirectory => {
                     self.expect(Tok::Lparen)?;
-                    self.expect(Tok::Via)?;
-                    while self.peek(0)?.tok != Tok::Rparen {
-                        v.vias.push(self.literal()?.to_string());
-                    }
+                    self.expect(Tok::ExtraImageDirectory)?;
+                    v.extra_image_directory = Some(self.literal()?.to_string());
                     self.expect(Tok::Rparen)?;
                 }
+                Tok::FamilyFamily => v.family_families.push(self.family_family()?),
+                Tok::Image => v.images.push(self.image()?),
+                Tok::ImageImage => v.image_images.push(self.image_image()?),
+                Tok::Jumper => v.jumpers.push(self.jumper()?),
+                Tok::Padstack => v.padstacks.push(self.padstack()?),
+                Tok::ViaArrayTemplate => v.via_array_templates.push(self.via_array_template()?),
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -190,15 +350,16 @@ impl Parser {
         Ok(v)
     }
 
-    fn wiring(&mut self) -> Result<DsnWiring> {
-        let mut v = DsnWiring::default();
+    fn jumper(&mut self) -> Result<DsnJumper> {
+        let mut v = DsnJumper::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Wiring)?;
+        self.expect(Tok::Jumper)?;
+        v.length = self.paren_number(Tok::Length)?;
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Wire => v.wires.push(self.wire()?),
-                Tok::Via => v.vias.push(self.via()?),
+                Tok::Height => v.height = Some(self.paren_number(Tok::Height)?),
+                Tok::UseVia => v.use_via = self.paren_id(Tok::UseVia)?,
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -206,45 +367,96 @@ impl Parser {
         Ok(v)
     }
 
-    fn via(&mut self) -> Result<DsnVia> {
-        let v = DsnVia::default();
+    fn via_array_template(&mut self) -> Result<DsnViaArrayTemplate> {
+        let mut v = DsnViaArrayTemplate::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Via)?;
-        // TODO: Finish.
+        self.expect(Tok::ViaArrayTemplate)?;
+        v.template_id = self.literal()?.to_string();
+        v.rows = self.integer()?;
+        v.columns = self.integer()?;
+        v.row_spacing = self.number()?;
+        v.column_spacing = self.number()?;
+        if self.peek(0)?.tok != Tok::Rparen {
+            v.use_via = self.paren_id(Tok::UseVia)?;
+        }
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn wire(&mut self) -> Result<DsnWire> {
-        let v = DsnWire::default();
+    fn family_family(&mut self) -> Result<DsnFamilyFamily> {
+        let mut v = DsnFamilyFamily::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Wire)?;
-        // TODO: Finish.
+        self.expect(Tok::FamilyFamily)?;
+        v.family_ids = (self.paren_id(Tok::Family)?, self.paren_id(Tok::Family)?);
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::PlaceRule => v.place_rules.extend(self.place_rule()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn layer(&mut self) -> Result<DsnLayer> {
-        let mut v = DsnLayer::default();
+    fn image_image(&mut self) -> Result<DsnImageImage> {
+        let mut v = DsnImageImage::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Layer)?;
-        v.layer_name = self.literal()?.to_string();
+        self.expect(Tok::ImageImage)?;
+        v.image_ids = (self.paren_id(Tok::Image)?, self.paren_id(Tok::Image)?);
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Type => {
+                Tok::PlaceRule => v.place_rules.extend(self.place_rule()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn network(&mut self) -> Result<DsnNetwork> {
+        let mut v = DsnNetwork::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Network)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Bundle => v.bundles.push(self.bundle()?),
+                Tok::Class => v.classes.push(self.class()?),
+                Tok::ClassClass => v.class_classes.push(self.class_class()?),
+                Tok::Group => v.groups.push(self.group()?),
+                Tok::GroupSet => v.group_sets.push(self.group_set()?),
+                Tok::Net => v.nets.push(self.net()?),
+                Tok::Pair => v.pairs.push(self.pair()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn placement(&mut self) -> Result<DsnPlacement> {
+        let mut v = DsnPlacement::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Placement)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Component => v.components.push(self.component()?),
+                Tok::PlaceControl => {
                     self.expect(Tok::Lparen)?;
-                    self.expect(Tok::Type)?;
-                    match self.next()?.tok {
-                        Tok::Jumper => v.layer_type = DsnLayerType::Jumper,
-                        Tok::Mixed => v.layer_type = DsnLayerType::Mixed,
-                        Tok::Power => v.layer_type = DsnLayerType::Power,
-                        Tok::Signal => v.layer_type = DsnLayerType::Signal,
-                        _ => return Err(eyre!("unrecognised layer type")),
+                    self.expect(Tok::PlaceControl)?;
+                    if self.peek(0)?.tok == Tok::Lparen {
+                        self.expect(Tok::Lparen)?;
+                        self.expect(Tok::FlipStyle)?;
+                        v.flip_style = Some(self.keyword("flip style")?);
+                        self.expect(Tok::Rparen)?;
                     }
                     self.expect(Tok::Rparen)?;
                 }
-                Tok::Property => self.ignore()?, // Ignore user properties.
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -252,56 +464,116 @@ impl Parser {
         Ok(v)
     }
 
-    fn plane(&mut self) -> Result<DsnPlane> {
-        let v = DsnPlane::default();
+    fn resolution(&mut self) -> Result<DsnResolution> {
+        let mut v = DsnResolution::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Plane)?;
-        // TODO: Finish.
+        self.expect(Tok::Resolution)?;
+        v.dimension = self.dimension()?;
+        v.amount = self.integer()?;
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn component(&mut self) -> Result<DsnComponent> {
-        let mut v = DsnComponent::default();
+    fn electrical_resolution(
+        &mut self,
+        t: Tok,
+        expected: DsnElectricalUnit,
+    ) -> Result<DsnElectricalResolution> {
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Component)?;
-        v.image_id = self.literal()?.to_string();
+        self.expect(t)?;
+        let unit: DsnElectricalUnit = self.keyword("electrical unit")?;
+        if unit != expected {
+            return Err(eyre!("expected unit {} but got {}", expected, unit));
+        }
+        let v = DsnElectricalResolution { unit, amount: self.integer()? };
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn colors(&mut self) -> Result<DsnColors> {
+        let mut v = DsnColors::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Colors)?;
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Place => v.refs.push(self.placement_ref()?),
-                _ => return Err(eyre!("unrecognised token '{}'", t)),
+                Tok::Color => v.colors.push(self.color()?),
+                Tok::SetColor => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::SetColor)?;
+                    let object_id = self.literal()?.to_string();
+                    v.set_colors.push(DsnSetColor { object_id, color_id: self.integer()? });
+                    self.expect(Tok::Rparen)?;
+                }
+                _ => v.unknown.push(self.sexpr()?),
             }
         }
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn placement_ref(&mut self) -> Result<DsnPlacementRef> {
-        let mut v = DsnPlacementRef::default();
+    fn color(&mut self) -> Result<DsnColor> {
+        let mut v = DsnColor::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Place)?;
-        v.component_id = self.literal()?.to_string();
-        v.p = self.vertex()?; // Assume we have vertex information.
-        v.side = self.side()?;
-        v.rotation = self.number()?;
+        self.expect(Tok::Color)?;
+        v.color_id = self.integer()?;
+        if self.peek(3)?.tok != Tok::Rparen {
+            v.name = self.literal()?.to_string();
+        }
+        v.rgb = (self.color_component()?, self.color_component()?, self.color_component()?);
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    // Parses a single color component in [0, 255].
+    fn color_component(&mut self) -> Result<u8> {
+        let c = self.integer()?;
+        u8::try_from(c).map_err(|_| eyre!("color component {} out of range", c))
+    }
+
+    fn structure(&mut self) -> Result<DsnStructure> {
+        let mut v = DsnStructure::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Structure)?;
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::LockType => {
+                Tok::Boundary => {
                     self.expect(Tok::Lparen)?;
-                    self.expect(Tok::LockType)?;
-                    match self.next()?.tok {
-                        Tok::Gate => v.lock_type = DsnLockType::Gate,
-                        Tok::Position => v.lock_type = DsnLockType::Position,
-                        _ => return Err(eyre!("unrecognised lock type")),
+                    self.expect(Tok::Boundary)?;
+                    v.boundaries.push(self.shape()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Control => v.control = Some(self.control()?),
+                Tok::Grid => v.grids.push(self.grid()?),
+                Tok::Keepout
+                | Tok::PlaceKeepout
+                | Tok::ViaKeepout
+                | Tok::WireKeepout
+                | Tok::BendKeepout
+                | Tok::ElongateKeepout => {
+                    v.keepouts.push(self.keepout()?);
+                }
+                Tok::Layer => v.layers.push(self.layer()?),
+                Tok::LayerNoiseWeight => v.layer_noise_weights.extend(self.layer_noise_weight()?),
+                Tok::PlaceBoundary => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::PlaceBoundary)?;
+                    while self.peek(0)?.tok != Tok::Rparen {
+                        v.place_boundary.push(self.shape()?);
                     }
                     self.expect(Tok::Rparen)?;
                 }
-                Tok::Pn => {
+                Tok::PlaceRule => v.place_rules.extend(self.place_rule()?),
+                Tok::Plane => v.planes.push(self.plane()?),
+                Tok::Region => v.regions.push(self.region()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                Tok::Via => {
                     self.expect(Tok::Lparen)?;
-                    self.expect(Tok::Pn)?;
-                    v.part_number = self.literal()?.to_string();
+                    self.expect(Tok::Via)?;
+                    while self.peek(0)?.tok != Tok::Rparen {
+                        v.vias.push(self.literal()?.to_string());
+                    }
                     self.expect(Tok::Rparen)?;
                 }
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
@@ -311,46 +583,54 @@ impl Parser {
         Ok(v)
     }
 
-    fn image(&mut self) -> Result<DsnImage> {
-        let mut v = DsnImage::default();
+    fn control(&mut self) -> Result<DsnControl> {
+        let mut v = DsnControl::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Image)?;
-        v.image_id = self.literal()?.to_string();
+        self.expect(Tok::Control)?;
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Outline => {
-                    self.expect(Tok::Lparen)?;
-                    self.expect(Tok::Outline)?;
-                    v.outlines.push(self.shape()?);
-                    self.expect(Tok::Rparen)?;
+                Tok::CheckingTrimByPin => {
+                    v.checking_trim_by_pin = Some(self.paren_onoff(Tok::CheckingTrimByPin)?);
                 }
-                Tok::Pin => v.pins.push(self.pin()?),
-                Tok::Keepout | Tok::ViaKeepout | Tok::WireKeepout => {
-                    v.keepouts.push(self.keepout()?);
+                Tok::ForceToTerminalPoint => {
+                    v.force_to_terminal_point = Some(self.paren_onoff(Tok::ForceToTerminalPoint)?);
                 }
-                _ => return Err(eyre!("unrecognised token '{}'", t)),
+                Tok::OffGrid => v.off_grid = Some(self.paren_onoff(Tok::OffGrid)?),
+                Tok::RouteToFanoutOnly => {
+                    v.route_to_fanout_only = Some(self.paren_onoff(Tok::RouteToFanoutOnly)?);
+                }
+                Tok::SameNetChecking => {
+                    v.same_net_checking = Some(self.paren_onoff(Tok::SameNetChecking)?);
+                }
+                Tok::ViaAtSmd => v.via_at_smd = Some(self.via_at_smd()?),
+                _ => v.unknown.push(self.sexpr()?),
             }
         }
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn keepout(&mut self) -> Result<DsnKeepout> {
-        let mut v = DsnKeepout::default();
+    fn grid(&mut self) -> Result<DsnGrid> {
         self.expect(Tok::Lparen)?;
-        v.keepout_type = match self.next()?.tok {
-            Tok::Keepout => DsnKeepoutType::Keepout,
-            Tok::ViaKeepout => DsnKeepoutType::ViaKeepout,
-            Tok::WireKeepout => DsnKeepoutType::WireKeepout,
-            _ => return Err(eyre!("unrecognised keepout type")),
+        self.expect(Tok::Grid)?;
+        let mut v = DsnGrid {
+            grid_type: self.keyword("grid type")?,
+            amount: self.number()?,
+            direction: None,
+            offset: None,
+            image_type: None,
         };
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Rect | Tok::Circle | Tok::Polygon | Tok::Path | Tok::Qarc => {
-                    v.shape = self.shape()?;
+                Tok::Direction => {
+                    v.direction = Some(self.paren_keyword(Tok::Direction, "grid direction")?);
                 }
+                Tok::ImageType => {
+                    v.image_type = Some(self.paren_keyword(Tok::ImageType, "image type")?);
+                }
+                Tok::Offset => v.offset = Some(self.paren_number(Tok::Offset)?),
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -358,39 +638,97 @@ impl Parser {
         Ok(v)
     }
 
-    fn pin(&mut self) -> Result<DsnPin> {
-        let mut v = DsnPin::default();
+    fn layer_noise_weight(&mut self) -> Result<Vec<DsnLayerNoiseWeight>> {
+        let mut v = Vec::new();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Pin)?;
-        v.padstack_id = self.literal()?.to_string();
-        if self.peek(0)?.tok == Tok::Lparen {
-            // Rotation.
+        self.expect(Tok::LayerNoiseWeight)?;
+        while self.peek(0)?.tok != Tok::Rparen {
             self.expect(Tok::Lparen)?;
-            self.expect(Tok::Rotate)?;
-            v.rotation = self.number()?;
+            self.expect(Tok::LayerPair)?;
+            let layer_pair = (self.literal()?.to_string(), self.literal()?.to_string());
+            v.push(DsnLayerNoiseWeight { layer_pair, weight: self.number()? });
             self.expect(Tok::Rparen)?;
         }
-        v.pin_id = self.literal()?.to_string();
-        v.p = self.vertex()?;
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn padstack(&mut self) -> Result<DsnPadstack> {
-        let mut v = DsnPadstack::default();
+    fn wiring(&mut self) -> Result<DsnWiring> {
+        let mut v = DsnWiring::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Padstack)?;
-        v.padstack_id = self.literal()?.to_string();
+        self.expect(Tok::Wiring)?;
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Attach => {
+                Tok::Wire => v.wires.push(self.wire()?),
+                Tok::Via => v.vias.push(self.via()?),
+                Tok::SupplyPin => v.supply_pins.push(self.supply_pin()?),
+                Tok::TestPoints => v.test_points.extend(self.test_points()?),
+                Tok::VirtualPin => v.virtual_pins.push(self.virtual_pin()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn via(&mut self) -> Result<DsnVia> {
+        let mut v = DsnVia::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Via)?;
+        v.padstack_id = self.literal()?.to_string();
+        while !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.pts.push(self.vertex()?);
+        }
+        if v.pts.is_empty() {
+            return Err(eyre!("via must have at least one vertex"));
+        }
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Attr => {
                     self.expect(Tok::Lparen)?;
-                    self.expect(Tok::Attach)?;
-                    v.attach = self.onoff()?;
+                    self.expect(Tok::Attr)?;
+                    v.attr = Some(if self.peek(0)?.tok == Tok::VirtualPin {
+                        self.expect(Tok::VirtualPin)?;
+                        DsnViaAttr::VirtualPin(self.literal()?.to_string())
+                    } else {
+                        self.keyword("via attribute")?
+                    });
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Contact => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Contact)?;
+                    while self.peek(0)?.tok != Tok::Rparen {
+                        v.contact_layers.push(self.literal()?.to_string());
+                    }
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Net => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Net)?;
+                    v.net_id = self.literal()?.to_string();
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Supply => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Supply)?;
+                    v.supply = true;
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Type => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Type)?;
+                    v.via_type = Some(self.wire_type()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::ViaNumber => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::ViaNumber)?;
+                    v.via_number = Some(self.integer()?);
                     self.expect(Tok::Rparen)?;
                 }
-                Tok::Shape => v.shapes.push(self.padstack_shape()?),
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -398,166 +736,220 @@ impl Parser {
         Ok(v)
     }
 
-    fn padstack_shape(&mut self) -> Result<DsnPadstackShape> {
-        let mut v = DsnPadstackShape::default();
+    fn virtual_pin(&mut self) -> Result<DsnVirtualPin> {
+        let mut v = DsnVirtualPin::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Shape)?;
-        v.shape = self.shape()?;
+        self.expect(Tok::VirtualPin)?;
+        v.name = self.literal()?.to_string();
+        v.p = self.vertex()?;
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Net)?;
+        v.net_id = self.literal()?.to_string();
+        self.expect(Tok::Rparen)?;
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn clearance(&mut self) -> Result<DsnClearance> {
-        let mut v = DsnClearance::default();
+    fn test_points(&mut self) -> Result<Vec<DsnTestPoint>> {
+        let mut v = Vec::new();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Clearance)?;
-        v.amount = self.number()?;
-
+        self.expect(Tok::TestPoints)?;
         while self.peek(0)?.tok != Tok::Rparen {
-            self.expect(Tok::Lparen)?;
-            self.expect(Tok::Type)?;
-            v.types.push(match self.next()?.tok {
-                Tok::DefaultSmd => DsnClearanceType::DefaultSmd,
-                Tok::SmdSmd => DsnClearanceType::SmdSmd,
-                _ => return Err(eyre!("unrecognised clearance type")),
-            });
-            self.expect(Tok::Rparen)?;
-        }
-
-        // If no type is specified, assume it applies to everything.
-        if v.types.is_empty() {
-            v.types.push(DsnClearanceType::All);
+            v.push(self.test_point()?);
         }
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    #[allow(dead_code)]
-    fn window(&mut self) -> Result<DsnWindow> {
-        let v = DsnWindow::default();
+    fn test_point(&mut self) -> Result<DsnTestPoint> {
+        let mut v = DsnTestPoint::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Window)?;
-        // TODO: Finish.
-        self.expect(Tok::Rparen)?;
-        Ok(v)
-    }
-
-    fn shape(&mut self) -> Result<DsnShape> {
-        match self.peek(1)?.tok {
-            Tok::Circle => Ok(DsnShape::Circle(self.circle()?)),
-            Tok::Path => Ok(DsnShape::Path(self.path()?)),
-            Tok::Polygon => Ok(DsnShape::Polygon(self.polygon()?)),
-            Tok::Qarc => Ok(DsnShape::QArc(self.qarc()?)),
-            Tok::Rect => Ok(DsnShape::Rect(self.rect()?)),
-            _ => Err(eyre!("unrecognised shape type")),
+        self.expect(Tok::Point)?;
+        v.p = self.vertex()?;
+        if matches!(self.peek(0)?.tok, Tok::Back | Tok::Front) {
+            v.side = Some(self.side()?);
+        }
+        if !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.layer_id = self.literal()?.to_string();
+        }
+        if self.peek(0)?.tok != Tok::Rparen {
+            v.net_id = self.paren_id(Tok::Net)?;
         }
-    }
-
-    fn rect(&mut self) -> Result<DsnRect> {
-        let mut v = DsnRect::default();
-        self.expect(Tok::Lparen)?;
-        self.expect(Tok::Rect)?;
-        v.layer_id = self.literal()?.to_string();
-        let a = self.vertex()?;
-        let b = self.vertex()?;
-        v.rect = Rt::enclosing(a, b); // Opposite points but can be in either order.
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn circle(&mut self) -> Result<DsnCircle> {
-        let mut v = DsnCircle::default();
+    fn supply_pin(&mut self) -> Result<DsnSupplyPin> {
+        let mut v = DsnSupplyPin::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Circle)?;
-        v.layer_id = self.literal()?.to_string();
-        v.diameter = self.number()?;
+        self.expect(Tok::SupplyPin)?;
+        while !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.pins.push(self.pin_ref()?);
+        }
         if self.peek(0)?.tok != Tok::Rparen {
-            v.p = self.vertex()?;
+            v.net_id = self.paren_id(Tok::Net)?;
         }
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn polygon(&mut self) -> Result<DsnPolygon> {
-        let mut v = DsnPolygon::default();
+    fn wire(&mut self) -> Result<DsnWire> {
+        let mut v = DsnWire::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Polygon)?;
-        v.layer_id = self.literal()?.to_string();
-        v.aperture_width = self.number()?;
+        self.expect(Tok::Wire)?;
+        v.shape = match self.peek(1)?.tok {
+            Tok::Path | Tok::Polygon | Tok::Qarc | Tok::Rect => self.shape()?,
+            _ => return Err(eyre!("unrecognised wire shape type")),
+        };
         while self.peek(0)?.tok != Tok::Rparen {
-            v.pts.push(self.vertex()?);
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Attr => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Attr)?;
+                    v.attr = Some(self.keyword("wire attribute")?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Connect => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Connect)?;
+                    let a = self.terminal()?;
+                    let b = self.terminal()?;
+                    v.connect = Some((a, b));
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Net => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Net)?;
+                    v.net_id = self.literal()?.to_string();
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Shield => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Shield)?;
+                    v.shield_net_id = self.literal()?.to_string();
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Supply => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Supply)?;
+                    v.supply = true;
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Turret => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Turret)?;
+                    v.turret = Some(self.integer()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Type => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Type)?;
+                    v.wire_type = Some(self.wire_type()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Window => v.windows.push(self.window()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
         }
         self.expect(Tok::Rparen)?;
-        if v.pts.len() < 3 {
-            return Err(eyre!("polygon must have at least three points"));
-        }
         Ok(v)
     }
 
-    fn path(&mut self) -> Result<DsnPath> {
-        let mut v = DsnPath::default();
+    fn terminal(&mut self) -> Result<DsnTerminal> {
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Path)?;
-        v.layer_id = self.literal()?.to_string();
-        v.aperture_width = self.number()?;
+        self.expect(Tok::Terminal)?;
+        let object_type = self.object_type()?;
+        let pin_ref = if self.peek(0)?.tok == Tok::Rparen { None } else { Some(self.pin_ref()?) };
+        self.expect(Tok::Rparen)?;
+        Ok(DsnTerminal { object_type, pin_ref })
+    }
+
+    fn layer(&mut self) -> Result<DsnLayer> {
+        let mut v = DsnLayer::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Layer)?;
+        v.layer_name = self.literal()?.to_string();
         while self.peek(0)?.tok != Tok::Rparen {
-            v.pts.push(self.vertex()?);
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Type => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Type)?;
+                    match self.next()?.tok {
+                        Tok::Jumper => v.layer_type = DsnLayerType::Jumper,
+                        Tok::Mixed => v.layer_type = DsnLayerType::Mixed,
+                        Tok::Power => v.layer_type = DsnLayerType::Power,
+                        Tok::Signal => v.layer_type = DsnLayerType::Signal,
+                        _ => return Err(eyre!("unrecognised layer type")),
+                    }
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Cost => v.cost = Some(self.layer_cost()?),
+                Tok::Direction => {
+                    v.direction = Some(self.paren_keyword(Tok::Direction, "direction")?);
+                }
+                Tok::Property => v.properties.extend(self.property()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                Tok::UseNet => v.use_net = self.ids(Tok::UseNet)?,
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
         }
         self.expect(Tok::Rparen)?;
-        if v.pts.len() < 2 {
-            return Err(eyre!("path must have at least two points"));
-        }
         Ok(v)
     }
 
-    fn qarc(&mut self) -> Result<DsnQArc> {
-        let mut v = DsnQArc::default();
+    fn layer_cost(&mut self) -> Result<DsnLayerCost> {
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Qarc)?;
-        v.layer_id = self.literal()?.to_string();
-        v.aperture_width = self.number()?;
-        v.start = self.vertex()?;
-        v.end = self.vertex()?;
-        v.center = self.vertex()?;
+        self.expect(Tok::Cost)?;
+        let s = self.literal()?.to_lowercase();
+        let cost = match s.as_str() {
+            "forbidden" => DsnCost::Forbidden,
+            "high" => DsnCost::High,
+            "medium" => DsnCost::Medium,
+            "low" => DsnCost::Low,
+            "free" => DsnCost::Free,
+            _ => DsnCost::Value(
+                DsnNumber::from_str(&s)
+                    .ok()
+                    .and_then(|n| n.to_i32())
+                    .ok_or_else(|| eyre!("unrecognised cost {}", s))?,
+            ),
+        };
+        let mut cost_type = None;
+        if self.peek(0)?.tok == Tok::Lparen {
+            cost_type = Some(self.paren_keyword(Tok::Type, "cost type")?);
+        }
         self.expect(Tok::Rparen)?;
-        Ok(v)
+        Ok(DsnLayerCost { cost, cost_type })
     }
 
-    fn class(&mut self) -> Result<DsnClass> {
-        let mut v = DsnClass::default();
+    fn plane(&mut self) -> Result<DsnPlane> {
+        let mut v = DsnPlane::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Class)?;
-        v.class_id = self.literal()?.to_string();
+        self.expect(Tok::Plane)?;
+        v.net_id = self.literal()?.to_string();
+        v.shape = self.shape()?;
         while self.peek(0)?.tok != Tok::Rparen {
-            let pt = self.peek(0)?;
-            if pt.tok == Tok::Lparen {
-                let t = self.peek(1)?;
-                match t.tok {
-                    Tok::Circuit => v.circuits.extend(self.circuit()?),
-                    Tok::Rule => v.rules.extend(self.rule()?),
-                    _ => return Err(eyre!("unrecognised token '{}'", t)),
-                }
-            } else {
-                v.net_ids.push(self.literal()?.to_string());
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Window => v.windows.push(self.window()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
         self.expect(Tok::Rparen)?;
         Ok(v)
     }
 
-    fn circuit(&mut self) -> Result<Vec<DsnCircuit>> {
-        let mut v = Vec::new();
+    fn component(&mut self) -> Result<DsnComponent> {
+        let mut v = DsnComponent::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Circuit)?;
+        self.expect(Tok::Component)?;
+        v.image_id = self.literal()?.to_string();
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::UseVia => {
-                    self.expect(Tok::Lparen)?;
-                    self.expect(Tok::UseVia)?;
-                    v.push(DsnCircuit::UseVia(self.literal()?.to_string()));
-                    self.expect(Tok::Rparen)?;
-                }
+                Tok::Place => v.refs.push(self.placement_ref()?),
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -565,22 +957,62 @@ impl Parser {
         Ok(v)
     }
 
-    fn net(&mut self) -> Result<DsnNet> {
-        let mut v = DsnNet::default();
+    fn placement_ref(&mut self) -> Result<DsnPlacementRef> {
+        let mut v = DsnPlacementRef::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Net)?;
-        v.net_id = self.literal()?.to_string();
+        self.expect(Tok::Place)?;
+        v.component_id = self.literal()?.to_string();
+        // Unplaced components have no location information.
+        if !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.p = Some(self.vertex()?);
+            v.side = self.side()?;
+            v.rotation = self.number()?;
+        }
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Pins => {
+                Tok::LockType => {
                     self.expect(Tok::Lparen)?;
-                    self.expect(Tok::Pins)?;
+                    self.expect(Tok::LockType)?;
                     while self.peek(0)?.tok != Tok::Rparen {
-                        v.pins.push(self.pin_ref()?);
+                        v.lock_types.push(match self.next()?.tok {
+                            Tok::Gate => DsnLockType::Gate,
+                            Tok::Pin => DsnLockType::Pin,
+                            Tok::Position => DsnLockType::Position,
+                            Tok::Subgate => DsnLockType::Subgate,
+                            _ => return Err(eyre!("unrecognised lock type")),
+                        });
                     }
                     self.expect(Tok::Rparen)?;
                 }
+                Tok::LogicalPart => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::LogicalPart)?;
+                    v.logical_part = self.literal()?.to_string();
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Mirror => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Mirror)?;
+                    v.mirror = self.keyword("mirror type")?;
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::PlaceRule => v.place_rules.extend(self.place_rule()?),
+                Tok::Property => v.properties.extend(self.property()?),
+                Tok::Region => v.region = Some(self.region()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                Tok::Status => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Status)?;
+                    v.status = Some(self.keyword("component status")?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Pn => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Pn)?;
+                    v.part_number = self.literal()?.to_string();
+                    self.expect(Tok::Rparen)?;
+                }
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -588,21 +1020,46 @@ impl Parser {
         Ok(v)
     }
 
-    fn rule(&mut self) -> Result<Vec<DsnRule>> {
-        let mut v = Vec::new();
+    fn image(&mut self) -> Result<DsnImage> {
+        let mut v = DsnImage::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Rule)?;
+        self.expect(Tok::Image)?;
+        v.image_id = self.literal()?.to_string();
         while self.peek(0)?.tok != Tok::Rparen {
             let t = self.peek(1)?;
             match t.tok {
-                Tok::Width => {
+                Tok::Outline => {
                     self.expect(Tok::Lparen)?;
-                    self.expect(Tok::Width)?;
-                    let width = self.number()?;
-                    v.push(DsnRule::Width(width));
+                    self.expect(Tok::Outline)?;
+                    v.outlines.push(self.shape()?);
                     self.expect(Tok::Rparen)?;
                 }
-                Tok::Clearance => v.push(DsnRule::Clearance(self.clearance()?)),
+                Tok::Conductor => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Conductor)?;
+                    v.conductors.push(self.shape()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Keepout
+                | Tok::PlaceKeepout
+                | Tok::ViaKeepout
+                | Tok::WireKeepout
+                | Tok::BendKeepout
+                | Tok::ElongateKeepout => {
+                    v.keepouts.push(self.keepout()?);
+                }
+                Tok::Pin => self.pin(&mut v)?,
+                Tok::PlaceRule => v.place_rules.extend(self.place_rule()?),
+                Tok::Property => v.properties.extend(self.property()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                Tok::Side => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Side)?;
+                    v.side = Some(self.side()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Unit => v.unit = Some(self.unit()?),
+                Tok::Via => v.conductor_vias.push(self.via()?),
                 _ => return Err(eyre!("unrecognised token '{}'", t)),
             }
         }
@@ -610,687 +1067,3219 @@ impl Parser {
         Ok(v)
     }
 
-    fn vertex(&mut self) -> Result<Pt> {
-        Ok(pt(self.number()?, self.number()?))
+    fn keepout(&mut self) -> Result<DsnKeepout> {
+        let mut v = DsnKeepout::default();
+        self.expect(Tok::Lparen)?;
+        v.keepout_type = match self.next()?.tok {
+            Tok::Keepout => DsnKeepoutType::Keepout,
+            Tok::PlaceKeepout => DsnKeepoutType::PlaceKeepout,
+            Tok::ViaKeepout => DsnKeepoutType::ViaKeepout,
+            Tok::WireKeepout => DsnKeepoutType::WireKeepout,
+            Tok::BendKeepout => DsnKeepoutType::BendKeepout,
+            Tok::ElongateKeepout => DsnKeepoutType::ElongateKeepout,
+            _ => return Err(eyre!("unrecognised keepout type")),
+        };
+        if !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.keepout_id = self.literal()?.to_string();
+        }
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::PlaceRule => v.place_rules.extend(self.place_rule()?),
+                Tok::Rect | Tok::Circle | Tok::Polygon | Tok::Path | Tok::Qarc => {
+                    v.shape = self.shape()?;
+                }
+                Tok::Rule => v.rules.extend(self.rule()?),
+                Tok::SequenceNumber => {
+                    v.sequence_number = Some(self.paren_integer(Tok::SequenceNumber)?);
+                }
+                Tok::Window => v.windows.push(self.window()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
     }
 
-    fn unit(&mut self) -> Result<DsnDimensionUnit> {
+    // Pins may be either a single pin or a pin array, so this adds to |image|
+    // directly.
+    fn pin(&mut self, image: &mut DsnImage) -> Result<()> {
+        let mut v = DsnPin::default();
         self.expect(Tok::Lparen)?;
-        self.expect(Tok::Unit)?;
-        let v = self.dimension()?;
+        self.expect(Tok::Pin)?;
+        v.padstack_id = self.literal()?.to_string();
+        if self.peek(0)?.tok == Tok::Lparen && self.peek(1)?.tok == Tok::Rotate {
+            // Rotation.
+            self.expect(Tok::Lparen)?;
+            self.expect(Tok::Rotate)?;
+            v.rotation = self.number()?;
+            self.expect(Tok::Rparen)?;
+        }
+        if self.peek(0)?.tok == Tok::Lparen {
+            let mut array = self.pin_array()?;
+            array.padstack_id = v.padstack_id;
+            array.rotation = v.rotation;
+            image.pin_arrays.push(array);
+        } else {
+            v.pin_id = self.literal()?.to_string();
+            v.p = self.vertex()?;
+            image.pins.push(v);
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(())
+    }
+
+    fn pin_array(&mut self) -> Result<DsnPinArray> {
+        let mut v = DsnPinArray::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Array)?;
+        v.begin_index = self.integer()?;
+        v.count = self.integer()?;
+        v.x_pitch = self.number()?;
+        v.y_pitch = self.number()?;
+        v.p = self.vertex()?;
+        if self.peek(0)?.tok != Tok::Rparen {
+            v.prefix = self.literal()?.to_string();
+        }
         self.expect(Tok::Rparen)?;
+        v.validate()?;
         Ok(v)
     }
 
-    fn pin_ref(&mut self) -> Result<DsnPinRef> {
-        let p = self.literal()?;
-        let (a, b) = p.rsplit_once('-').ok_or_else(|| eyre!("invalid pin reference {}", p))?;
-        Ok(DsnPinRef { component_id: a.to_owned(), pin_id: b.to_owned() })
+    fn padstack(&mut self) -> Result<DsnPadstack> {
+        let mut v = DsnPadstack::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Padstack)?;
+        v.padstack_id = self.literal()?.to_string();
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Absolute => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Absolute)?;
+                    v.absolute = self.onoff()?;
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Attach => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Attach)?;
+                    v.attach = self.onoff()?;
+                    if self.peek(0)?.tok == Tok::Lparen {
+                        self.expect(Tok::Lparen)?;
+                        self.expect(Tok::UseVia)?;
+                        v.use_via = self.literal()?.to_string();
+                        self.expect(Tok::Rparen)?;
+                    }
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::ViaSite => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::ViaSite)?;
+                    v.pad_via_sites.push(self.vertex()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Rotate => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Rotate)?;
+                    v.rotate = self.onoff()?;
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Rule => v.rules.extend(self.rule()?),
+                Tok::Shape => v.shapes.push(self.padstack_shape()?),
+                Tok::Unit => v.unit = Some(self.unit()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
     }
 
-    fn onoff(&mut self) -> Result<bool> {
-        match self.next()?.tok {
-            Tok::Off => Ok(false),
-            Tok::On => Ok(true),
-            _ => Err(eyre!("expected off or on")),
+    fn padstack_shape(&mut self) -> Result<DsnPadstackShape> {
+        let mut v = DsnPadstackShape::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Shape)?;
+        v.shape = self.shape()?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Connect => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Connect)?;
+                    v.connect = self.onoff()?;
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Reduced => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Reduced)?;
+                    v.reduced = Some(self.shape()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Window => v.windows.push(self.window()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
         }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
     }
 
-    fn side(&mut self) -> Result<DsnSide> {
-        match self.next()?.tok {
-            Tok::Back => Ok(DsnSide::Back),
-            Tok::Both => Ok(DsnSide::Both),
-            Tok::Front => Ok(DsnSide::Front),
-            _ => Err(eyre!("unrecognised side type")),
+    fn clearance(&mut self) -> Result<DsnClearance> {
+        let mut v = DsnClearance::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Clearance)?;
+        v.amount = self.number()?;
+
+        while self.peek(0)?.tok != Tok::Rparen {
+            self.expect(Tok::Lparen)?;
+            self.expect(Tok::Type)?;
+            while self.peek(0)?.tok != Tok::Rparen {
+                v.types.push(self.clearance_type()?);
+            }
+            self.expect(Tok::Rparen)?;
+        }
+
+        // If no type is specified, assume it applies to everything.
+        if v.types.is_empty() {
+            v.types.push(DsnClearanceType::All);
         }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
     }
 
-    fn dimension(&mut self) -> Result<DsnDimensionUnit> {
-        Ok(match self.next()?.tok {
-            Tok::Inch => DsnDimensionUnit::Inch,
-            Tok::Mil => DsnDimensionUnit::Mil,
-            Tok::Cm => DsnDimensionUnit::Cm,
-            Tok::Mm => DsnDimensionUnit::Mm,
-            Tok::Um => DsnDimensionUnit::Um,
-            _ => return Err(eyre!("unknown dimension unit")),
+    fn clearance_type(&mut self) -> Result<DsnClearanceType> {
+        let s = self.literal()?.to_lowercase();
+        Ok(match s.as_str() {
+            "default_smd" => DsnClearanceType::DefaultSmd,
+            "smd_via_same_net" => DsnClearanceType::SmdViaSameNet,
+            "via_via_same_net" => DsnClearanceType::ViaViaSameNet,
+            "buried_via_gap" => {
+                let mut depth = None;
+                if self.peek(0)?.tok == Tok::Lparen {
+                    depth = Some(self.paren_integer(Tok::LayerDepth)?);
+                }
+                DsnClearanceType::BuriedViaGap(depth)
+            }
+            "antipad_gap" => DsnClearanceType::AntipadGap,
+            "pad_to_turn_gap" => DsnClearanceType::PadToTurnGap,
+            "smd_to_turn_gap" => DsnClearanceType::SmdToTurnGap,
+            _ => {
+                // Object types don't contain underscores, so this is unambiguous.
+                let (a, b) = s
+                    .split_once('_')
+                    .and_then(|(a, b)| {
+                        Some((DsnObjectType::from_str(a).ok()?, DsnObjectType::from_str(b).ok()?))
+                    })
+                    .ok_or_else(|| eyre!("unrecognised clearance type {}", s))?;
+                DsnClearanceType::Pair(a, b)
+            }
         })
     }
 
-    fn number(&mut self) -> Result<f64> {
-        // TODO: Handle fractions.
-        Ok(f64::from_str(self.literal()?)?)
+    fn window(&mut self) -> Result<DsnWindow> {
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Window)?;
+        let v = match self.peek(1)?.tok {
+            Tok::Polygon => DsnWindow::Polygon(self.polygon()?),
+            Tok::Rect => DsnWindow::Rect(self.rect()?),
+            _ => return Err(eyre!("unrecognised window shape type")),
+        };
+        self.expect(Tok::Rparen)?;
+        Ok(v)
     }
 
-    fn integer(&mut self) -> Result<i32> {
-        // TODO: Handle fractions.
-        Ok(i32::from_str(self.literal()?)?)
+    fn shape(&mut self) -> Result<DsnShape> {
+        match self.peek(1)?.tok {
+            Tok::Circle => Ok(DsnShape::Circle(self.circle()?)),
+            Tok::Path => Ok(DsnShape::Path(self.path()?)),
+            Tok::Polygon => Ok(DsnShape::Polygon(self.polygon()?)),
+            Tok::Qarc => Ok(DsnShape::QArc(self.qarc()?)),
+            Tok::Rect => Ok(DsnShape::Rect(self.rect()?)),
+            _ => Err(eyre!("unrecognised shape type")),
+        }
     }
-}
 
-#[cfg(test)]
-#[allow(clippy::float_cmp)]
-mod tests {
-    use super::*;
-    use crate::lexer::Lexer;
+    fn rect(&mut self) -> Result<DsnRect> {
+        let mut v = DsnRect::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Rect)?;
+        v.layer_id = self.literal()?.to_string();
+        let a = self.vertex()?;
+        let b = self.vertex()?;
+        v.rect = Rt::enclosing(a, b); // Opposite points but can be in either order.
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
 
-    fn parse_dsn(data: &str) -> Result<DsnPcb> {
-        let lexer = Lexer::new(data)?;
-        let tokens = lexer.lex()?;
-        let parser = Parser::new(&tokens);
-        parser.parse()
+    fn circle(&mut self) -> Result<DsnCircle> {
+        let mut v = DsnCircle::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Circle)?;
+        v.layer_id = self.literal()?.to_string();
+        v.diameter = self.number()?;
+        if self.peek(0)?.tok != Tok::Rparen {
+            v.p = self.vertex()?;
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn polygon(&mut self) -> Result<DsnPolygon> {
+        let mut v = DsnPolygon::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Polygon)?;
+        v.layer_id = self.literal()?.to_string();
+        v.aperture_width = self.number()?;
+        while !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.pts.push(self.vertex()?);
+        }
+        if self.peek(0)?.tok == Tok::Lparen {
+            v.aperture_type = self.paren_keyword(Tok::ApertureType, "aperture type")?;
+        }
+        self.expect(Tok::Rparen)?;
+        if v.pts.len() < 3 {
+            return Err(eyre!("polygon must have at least three points"));
+        }
+        Ok(v)
+    }
+
+    fn path(&mut self) -> Result<DsnPath> {
+        let mut v = DsnPath::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Path)?;
+        v.layer_id = self.literal()?.to_string();
+        v.aperture_width = self.number()?;
+        while !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.pts.push(self.vertex()?);
+        }
+        if self.peek(0)?.tok == Tok::Lparen {
+            v.aperture_type = self.paren_keyword(Tok::ApertureType, "aperture type")?;
+        }
+        self.expect(Tok::Rparen)?;
+        if v.pts.len() < 2 {
+            return Err(eyre!("path must have at least two points"));
+        }
+        Ok(v)
+    }
+
+    fn qarc(&mut self) -> Result<DsnQArc> {
+        let mut v = DsnQArc::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Qarc)?;
+        v.layer_id = self.literal()?.to_string();
+        v.aperture_width = self.number()?;
+        v.start = self.vertex()?;
+        v.end = self.vertex()?;
+        v.center = self.vertex()?;
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn class(&mut self) -> Result<DsnClass> {
+        let mut v = DsnClass::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Class)?;
+        v.class_id = self.literal()?.to_string();
+        while self.peek(0)?.tok != Tok::Rparen {
+            let pt = self.peek(0)?;
+            if pt.tok == Tok::Lparen {
+                let t = self.peek(1)?;
+                match t.tok {
+                    Tok::Circuit => v.circuits.extend(self.circuit()?),
+                    Tok::Composite => {
+                        let c = self.composite()?;
+                        v.net_ids.extend(c.names()?);
+                        v.composites.push(c);
+                    }
+                    Tok::LayerRule => v.layer_rules.push(self.layer_rule()?),
+                    Tok::Rule => v.rules.extend(self.rule()?),
+                    Tok::Topology => v.topology = Some(self.topology()?),
+                    _ => return Err(eyre!("unrecognised token '{}'", t)),
+                }
+            } else {
+                v.net_ids.push(self.literal()?.to_string());
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn composite(&mut self) -> Result<DsnCompositeNameList> {
+        let mut v = DsnCompositeNameList::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Composite)?;
+        if self.peek(1)?.tok == Tok::Prefix {
+            self.expect(Tok::Lparen)?;
+            self.expect(Tok::Prefix)?;
+            v.prefix = self.literal()?.to_string();
+            self.expect(Tok::Rparen)?;
+        }
+        v.begin_index = self.integer()?;
+        v.end_index = self.integer()?;
+        if !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.step = self.integer()?;
+        }
+        if self.peek(0)?.tok == Tok::Lparen {
+            self.expect(Tok::Lparen)?;
+            self.expect(Tok::Suffix)?;
+            v.suffix = self.literal()?.to_string();
+            self.expect(Tok::Rparen)?;
+        }
+        self.expect(Tok::Rparen)?;
+        v.count()?;
+        Ok(v)
+    }
+
+    fn topology(&mut self) -> Result<DsnTopology> {
+        let mut v = DsnTopology::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Topology)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Composite => v.composites.push(self.composite()?),
+                Tok::Fromto => v.fromtos.push(self.fromto()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn class_class(&mut self) -> Result<DsnClassClass> {
+        let mut v = DsnClassClass::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::ClassClass)?;
+        v.class_ids = self.ids(Tok::Classes)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::LayerRule => v.layer_rules.push(self.layer_rule()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn group(&mut self) -> Result<DsnGroup> {
+        let mut v = DsnGroup::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Group)?;
+        v.group_id = self.literal()?.to_string();
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Circuit => v.circuits.extend(self.circuit()?),
+                Tok::Fromto => v.fromtos.push(self.fromto()?),
+                Tok::LayerRule => v.layer_rules.push(self.layer_rule()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn group_set(&mut self) -> Result<DsnGroupSet> {
+        let mut v = DsnGroupSet::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::GroupSet)?;
+        v.group_set_id = self.literal()?.to_string();
+        while self.peek(0)?.tok != Tok::Rparen {
+            if self.peek(0)?.tok != Tok::Lparen {
+                v.group_ids.push(self.literal()?.to_string());
+                continue;
+            }
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Circuit => v.circuits.extend(self.circuit()?),
+                Tok::LayerRule => v.layer_rules.push(self.layer_rule()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn pair(&mut self) -> Result<DsnPair> {
+        let mut v = DsnPair::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Pair)?;
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Nets)?;
+        v.net_ids = (self.literal()?.to_string(), self.literal()?.to_string());
+        self.expect(Tok::Rparen)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::LayerRule => v.layer_rules.push(self.layer_rule()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn bundle(&mut self) -> Result<DsnBundle> {
+        let mut v = DsnBundle::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Bundle)?;
+        v.bundle_id = self.literal()?.to_string();
+        v.net_ids = self.ids(Tok::Nets)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Gap => {
+                    let mut gap = DsnBundleGap::default();
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Gap)?;
+                    gap.amount = self.number()?;
+                    if self.peek(0)?.tok == Tok::Lparen {
+                        gap.layer_ids = self.ids(Tok::Layer)?;
+                    }
+                    self.expect(Tok::Rparen)?;
+                    v.gaps.push(gap);
+                }
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn circuit(&mut self) -> Result<Vec<DsnCircuit>> {
+        let mut v = Vec::new();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Circuit)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            let c = match t.tok {
+                Tok::Delay => DsnCircuit::Delay(self.limit(t.tok)?),
+                Tok::Length => DsnCircuit::Length(self.limit(t.tok)?),
+                Tok::MatchFromtoDelay => DsnCircuit::MatchFromtoDelay(self.length_match(t.tok)?),
+                Tok::MatchFromtoLength => DsnCircuit::MatchFromtoLength(self.length_match(t.tok)?),
+                Tok::MatchGroupDelay => DsnCircuit::MatchGroupDelay(self.length_match(t.tok)?),
+                Tok::MatchGroupLength => DsnCircuit::MatchGroupLength(self.length_match(t.tok)?),
+                Tok::MatchNetDelay => DsnCircuit::MatchNetDelay(self.length_match(t.tok)?),
+                Tok::MatchNetLength => DsnCircuit::MatchNetLength(self.length_match(t.tok)?),
+                Tok::MaxRestrictedLayerLength => {
+                    DsnCircuit::MaxRestrictedLayerLength(self.paren_number(t.tok)?)
+                }
+                Tok::Priority => DsnCircuit::Priority(self.paren_integer(t.tok)?),
+                Tok::RelativeDelay => DsnCircuit::RelativeDelay(self.relative(t.tok)?),
+                Tok::RelativeGroupDelay => DsnCircuit::RelativeGroupDelay(self.relative(t.tok)?),
+                Tok::RelativeGroupLength => DsnCircuit::RelativeGroupLength(self.relative(t.tok)?),
+                Tok::RelativeLength => DsnCircuit::RelativeLength(self.relative(t.tok)?),
+                Tok::SampleWindow => DsnCircuit::SampleWindow(self.time_window(t.tok)?),
+                Tok::Shield => DsnCircuit::Shield(self.shield()?),
+                Tok::SwitchWindow => DsnCircuit::SwitchWindow(self.time_window(t.tok)?),
+                Tok::TotalDelay => DsnCircuit::TotalDelay(self.limit(t.tok)?),
+                Tok::TotalLength => DsnCircuit::TotalLength(self.limit(t.tok)?),
+                Tok::UseLayer => DsnCircuit::UseLayer(self.ids(Tok::UseLayer)?),
+                Tok::UseVia => DsnCircuit::UseVia(self.use_via()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            };
+            v.push(c);
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn limit(&mut self, t: Tok) -> Result<DsnLimit> {
+        let mut v = DsnLimit::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        v.max = self.number()?;
+        if !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.min = Some(self.number()?);
+        }
+        if self.peek(0)?.tok == Tok::Lparen {
+            v.limit_type = Some(self.paren_keyword(Tok::Type, "limit type")?);
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn length_match(&mut self, t: Tok) -> Result<DsnMatch> {
+        let mut v = DsnMatch::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        if matches!(self.peek(0)?.tok, Tok::On | Tok::Off) {
+            v.on = self.onoff()?;
+        }
+        if !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.tolerance = Some(self.number()?);
+        }
+        if self.peek(0)?.tok == Tok::Lparen {
+            v.match_type = Some(self.paren_keyword(Tok::Type, "match type")?);
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn relative(&mut self, t: Tok) -> Result<DsnRelative> {
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        let v = DsnRelative {
+            reference_id: self.literal()?.to_string(),
+            delta: self.number()?,
+            tolerance: self.number()?,
+        };
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn time_window(&mut self, t: Tok) -> Result<DsnTimeWindow> {
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        let v = DsnTimeWindow { begin: self.number()?, end: self.number()? };
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn shield(&mut self) -> Result<DsnShield> {
+        let mut v = DsnShield::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Shield)?;
+        v.on = self.onoff()?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Type => v.shield_type = Some(self.paren_keyword(Tok::Type, "shield type")?),
+                Tok::UseNet => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::UseNet)?;
+                    v.net_id = self.literal()?.to_string();
+                    self.expect(Tok::Rparen)?;
+                }
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn use_via(&mut self) -> Result<Vec<DsnUseVia>> {
+        let mut v = Vec::new();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::UseVia)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            if self.peek(0)?.tok == Tok::Lparen {
+                self.expect(Tok::Lparen)?;
+                self.expect(Tok::UseArray)?;
+                let template_id = self.literal()?.to_string();
+                let (mut rows, mut columns) = (None, None);
+                if self.peek(0)?.tok != Tok::Rparen {
+                    rows = Some(self.integer()?);
+                    columns = Some(self.integer()?);
+                }
+                self.expect(Tok::Rparen)?;
+                v.push(DsnUseVia::Array { template_id, rows, columns });
+            } else {
+                v.push(DsnUseVia::Padstack(self.literal()?.to_string()));
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn net(&mut self) -> Result<DsnNet> {
+        let mut v = DsnNet::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Net)?;
+        v.net_id = self.literal()?.to_string();
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Circuit => v.circuits.extend(self.circuit()?),
+                Tok::Expose => v.expose = self.pin_refs(Tok::Expose)?,
+                Tok::Fromto => v.fromtos.push(self.fromto()?),
+                Tok::LayerRule => v.layer_rules.push(self.layer_rule()?),
+                Tok::Load => v.load = self.pin_refs(Tok::Load)?,
+                Tok::NetNumber => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::NetNumber)?;
+                    v.net_number = Some(self.integer()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Noexpose => v.noexpose = self.pin_refs(Tok::Noexpose)?,
+                Tok::Order => {
+                    v.pins = self.pin_refs(Tok::Order)?;
+                    v.ordered = true;
+                }
+                Tok::Pins => v.pins = self.pin_refs(Tok::Pins)?,
+                Tok::Property => v.properties.extend(self.property()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                Tok::Source => v.source = self.pin_refs(Tok::Source)?,
+                Tok::Supply => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Supply)?;
+                    v.supply = Some(self.keyword("supply type")?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Terminator => v.terminator = self.pin_refs(Tok::Terminator)?,
+                Tok::Type => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Type)?;
+                    v.net_type = Some(self.keyword("net type")?);
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Unassigned => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Unassigned)?;
+                    v.unassigned = true;
+                    self.expect(Tok::Rparen)?;
+                }
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn ids(&mut self, t: Tok) -> Result<Vec<DsnId>> {
+        let mut v = Vec::new();
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            v.push(self.literal()?.to_string());
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn pin_refs(&mut self, t: Tok) -> Result<Vec<DsnPinRef>> {
+        let mut v = Vec::new();
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            v.push(self.pin_ref()?);
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn layer_rule(&mut self) -> Result<DsnLayerRule> {
+        let mut v = DsnLayerRule::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::LayerRule)?;
+        while !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.layer_names.push(self.literal()?.to_string());
+        }
+        if v.layer_names.is_empty() {
+            return Err(eyre!("layer_rule must name at least one layer"));
+        }
+        if self.peek(1)?.tok != Tok::Rule {
+            return Err(eyre!("layer_rule must have a rule"));
+        }
+        v.rules = self.rule()?;
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn fromto(&mut self) -> Result<DsnFromTo> {
+        let mut v = DsnFromTo::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Fromto)?;
+        v.from = self.literal()?.to_string();
+        v.to = self.literal()?.to_string();
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Circuit => v.circuits.extend(self.circuit()?),
+                Tok::LayerRule => v.layer_rules.push(self.layer_rule()?),
+                Tok::Net => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Net)?;
+                    v.net_id = self.literal()?.to_string();
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Rule => v.rules.extend(self.rule()?),
+                Tok::Type => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Type)?;
+                    v.fromto_type = Some(self.keyword("fromto type")?);
+                    self.expect(Tok::Rparen)?;
+                }
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn rule(&mut self) -> Result<Vec<DsnRule>> {
+        let mut v = Vec::new();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Rule)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            let rule = match t.tok {
+                Tok::Width => DsnRule::Width(self.paren_number(Tok::Width)?),
+                Tok::Clearance => DsnRule::Clearance(self.clearance()?),
+                Tok::EffectiveViaLength => {
+                    DsnRule::EffectiveViaLength(self.paren_number(Tok::EffectiveViaLength)?)
+                }
+                Tok::InterLayerClearance => {
+                    DsnRule::InterLayerClearance(self.inter_layer_clearance()?)
+                }
+                Tok::JunctionType => {
+                    DsnRule::JunctionType(self.paren_keyword(Tok::JunctionType, "junction type")?)
+                }
+                Tok::LengthAmplitude => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::LengthAmplitude)?;
+                    let rule = DsnRule::LengthAmplitude(self.number()?, self.number()?);
+                    self.expect(Tok::Rparen)?;
+                    rule
+                }
+                Tok::LengthFactor => DsnRule::LengthFactor(self.paren_number(Tok::LengthFactor)?),
+                Tok::LengthGap => DsnRule::LengthGap(self.paren_number(Tok::LengthGap)?),
+                Tok::LimitBends => DsnRule::LimitBends(self.paren_integer(Tok::LimitBends)?),
+                Tok::LimitCrossing => {
+                    DsnRule::LimitCrossing(self.paren_integer(Tok::LimitCrossing)?)
+                }
+                Tok::LimitVias => DsnRule::LimitVias(self.paren_integer(Tok::LimitVias)?),
+                Tok::LimitWay => DsnRule::LimitWay(self.paren_integer(Tok::LimitWay)?),
+                Tok::MaxNoise => DsnRule::MaxNoise(self.paren_number(Tok::MaxNoise)?),
+                Tok::MaxStagger => DsnRule::MaxStagger(self.paren_number(Tok::MaxStagger)?),
+                Tok::MaxStub => DsnRule::MaxStub(self.paren_number(Tok::MaxStub)?),
+                Tok::MaxTotalVias => DsnRule::MaxTotalVias(self.paren_integer(Tok::MaxTotalVias)?),
+                Tok::ParallelNoise => DsnRule::ParallelNoise(self.noise(Tok::ParallelNoise)?),
+                Tok::ParallelSegment => {
+                    DsnRule::ParallelSegment(self.segment(Tok::ParallelSegment)?)
+                }
+                Tok::PinWidthTaper => DsnRule::PinWidthTaper(self.paren_onoff(Tok::PinWidthTaper)?),
+                Tok::PowerFanout => DsnRule::PowerFanout(self.paren_onoff(Tok::PowerFanout)?),
+                Tok::RedundantWiring => {
+                    DsnRule::RedundantWiring(self.paren_onoff(Tok::RedundantWiring)?)
+                }
+                Tok::Reorder => DsnRule::Reorder(self.paren_keyword(Tok::Reorder, "reorder type")?),
+                Tok::RestrictedLayerLengthFactor => DsnRule::RestrictedLayerLengthFactor(
+                    self.paren_number(Tok::RestrictedLayerLengthFactor)?,
+                ),
+                Tok::SaturationLength => {
+                    DsnRule::SaturationLength(self.paren_number(Tok::SaturationLength)?)
+                }
+                Tok::ShieldGap => DsnRule::ShieldGap(self.paren_number(Tok::ShieldGap)?),
+                Tok::ShieldLoop => {
+                    DsnRule::ShieldLoop(self.paren_keyword(Tok::ShieldLoop, "shield loop type")?)
+                }
+                Tok::ShieldTieDownInterval => {
+                    DsnRule::ShieldTieDownInterval(self.paren_number(Tok::ShieldTieDownInterval)?)
+                }
+                Tok::ShieldWidth => DsnRule::ShieldWidth(self.paren_number(Tok::ShieldWidth)?),
+                Tok::StackVia => DsnRule::StackVia(self.ids(Tok::StackVia)?),
+                Tok::StackViaDepth => DsnRule::StackViaDepth(self.stack_via_depth()?),
+                Tok::TandemNoise => DsnRule::TandemNoise(self.noise(Tok::TandemNoise)?),
+                Tok::TandemSegment => DsnRule::TandemSegment(self.segment(Tok::TandemSegment)?),
+                Tok::TandemShieldOverhang => {
+                    DsnRule::TandemShieldOverhang(self.paren_number(Tok::TandemShieldOverhang)?)
+                }
+                Tok::TestpointRule => DsnRule::TestpointRule(self.testpoint_rule()?),
+                Tok::TimeLengthFactor => {
+                    DsnRule::TimeLengthFactor(self.paren_number(Tok::TimeLengthFactor)?)
+                }
+                Tok::Tjunction => DsnRule::Tjunction(self.paren_onoff(Tok::Tjunction)?),
+                Tok::TrackId => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::TrackId)?;
+                    let rule = DsnRule::TrackId(self.literal()?.to_string());
+                    self.expect(Tok::Rparen)?;
+                    rule
+                }
+                Tok::ViaAtSmd => DsnRule::ViaAtSmd(self.via_at_smd()?),
+                Tok::ViaPattern => DsnRule::ViaPattern(self.paren_onoff(Tok::ViaPattern)?),
+                _ => DsnRule::Unknown(self.sexpr()?),
+            };
+            v.push(rule);
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn inter_layer_clearance(&mut self) -> Result<DsnInterLayerClearance> {
+        let mut v = DsnInterLayerClearance::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::InterLayerClearance)?;
+        v.amount = self.number()?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            v.layer_pairs.push(self.layer_pair()?);
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn noise(&mut self, t: Tok) -> Result<DsnNoise> {
+        let mut v = DsnNoise::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Gap => v.gap = Some(self.paren_number(Tok::Gap)?),
+                Tok::LayerPair => v.layer_pair = Some(self.layer_pair()?),
+                Tok::Threshold => v.threshold = Some(self.paren_number(Tok::Threshold)?),
+                Tok::Weight => v.weight = Some(self.paren_number(Tok::Weight)?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn segment(&mut self, t: Tok) -> Result<DsnSegment> {
+        let mut v = DsnSegment::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Gap => v.gap = Some(self.paren_number(Tok::Gap)?),
+                Tok::LayerPair => v.layer_pair = Some(self.layer_pair()?),
+                Tok::Limit => v.limit = Some(self.paren_number(Tok::Limit)?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn stack_via_depth(&mut self) -> Result<DsnStackViaDepth> {
+        let mut v = DsnStackViaDepth::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::StackViaDepth)?;
+        v.depth = self.integer()?;
+        if self.peek(0)?.tok != Tok::Rparen {
+            v.layer_pair = Some(self.layer_pair()?);
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn testpoint_rule(&mut self) -> Result<DsnTestpointRule> {
+        let mut v = DsnTestpointRule::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::TestpointRule)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::CenterCenter => v.center_center = Some(self.paren_number(Tok::CenterCenter)?),
+                Tok::CompEdgeCenter => {
+                    v.comp_edge_center = Some(self.paren_number(Tok::CompEdgeCenter)?);
+                }
+                Tok::PinAllow => v.pin_allow = Some(self.paren_onoff(Tok::PinAllow)?),
+                Tok::Side => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Side)?;
+                    v.side = Some(self.side()?);
+                    self.expect(Tok::Rparen)?;
+                }
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn via_at_smd(&mut self) -> Result<DsnViaAtSmd> {
+        let mut v = DsnViaAtSmd::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::ViaAtSmd)?;
+        v.allowed = self.onoff()?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Fit => v.fit = Some(self.paren_onoff(Tok::Fit)?),
+                Tok::Grid => v.grid = Some(self.paren_onoff(Tok::Grid)?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn layer_pair(&mut self) -> Result<(DsnLayerId, DsnLayerId)> {
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::LayerPair)?;
+        let v = (self.literal()?.to_string(), self.literal()?.to_string());
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn region(&mut self) -> Result<DsnRegion> {
+        let mut v = DsnRegion::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Region)?;
+        if !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            v.region_id = self.literal()?.to_string();
+        }
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::Class => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Class)?;
+                    v.class_id = self.literal()?.to_string();
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Net => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::Net)?;
+                    v.net_id = self.literal()?.to_string();
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Polygon | Tok::Rect => v.shape = Some(self.shape()?),
+                Tok::Rule => v.rules.extend(self.rule()?),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn place_rule(&mut self) -> Result<Vec<DsnPlaceRule>> {
+        let mut v = Vec::new();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::PlaceRule)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            let t = self.peek(1)?;
+            match t.tok {
+                Tok::OppositeSide => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::OppositeSide)?;
+                    v.push(DsnPlaceRule::OppositeSide(self.onoff()?));
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::PermitOrient => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::PermitOrient)?;
+                    v.push(DsnPlaceRule::PermitOrient(self.keyword("orientation")?));
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::PermitSide => {
+                    self.expect(Tok::Lparen)?;
+                    self.expect(Tok::PermitSide)?;
+                    let side = if self.peek(0)?.tok == Tok::Off {
+                        self.expect(Tok::Off)?;
+                        None
+                    } else {
+                        Some(self.side()?)
+                    };
+                    v.push(DsnPlaceRule::PermitSide(side));
+                    self.expect(Tok::Rparen)?;
+                }
+                Tok::Spacing => v.push(DsnPlaceRule::Spacing(self.spacing()?)),
+                _ => return Err(eyre!("unrecognised token '{}'", t)),
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn spacing(&mut self) -> Result<DsnSpacing> {
+        let mut v = DsnSpacing::default();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Spacing)?;
+        v.amount = self.number()?;
+        if self.peek(0)?.tok == Tok::Lparen {
+            self.expect(Tok::Lparen)?;
+            self.expect(Tok::Type)?;
+            v.spacing_type = self.literal()?.to_string();
+            self.expect(Tok::Rparen)?;
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn property(&mut self) -> Result<Vec<DsnProperty>> {
+        let mut v = Vec::new();
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Property)?;
+        while self.peek(0)?.tok != Tok::Rparen {
+            if self.peek(0)?.tok == Tok::Lparen {
+                self.expect(Tok::Lparen)?;
+                v.push(self.property_value()?);
+                self.expect(Tok::Rparen)?;
+            } else {
+                v.push(self.property_value()?);
+            }
+        }
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn property_value(&mut self) -> Result<DsnProperty> {
+        let key = self.literal()?.to_string();
+        let mut vals = Vec::new();
+        while !matches!(self.peek(0)?.tok, Tok::Lparen | Tok::Rparen) {
+            vals.push(self.literal()?.to_string());
+        }
+        Ok(DsnProperty { key, value: vals.join(" ") })
+    }
+
+    fn vertex(&mut self) -> Result<Pt> {
+        Ok(pt(self.number()?, self.number()?))
+    }
+
+    fn unit(&mut self) -> Result<DsnDimensionUnit> {
+        self.expect(Tok::Lparen)?;
+        self.expect(Tok::Unit)?;
+        let v = self.dimension()?;
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    fn pin_ref(&mut self) -> Result<DsnPinRef> {
+        let p = self.literal()?;
+        let (a, b) = p.rsplit_once('-').ok_or_else(|| eyre!("invalid pin reference {}", p))?;
+        Ok(DsnPinRef { component_id: a.to_owned(), pin_id: b.to_owned() })
+    }
+
+    // Parses (|t| <number>).
+    fn paren_number(&mut self, t: Tok) -> Result<f64> {
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        let v = self.number()?;
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    // Parses (|t| <id>).
+    fn paren_id(&mut self, t: Tok) -> Result<DsnId> {
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        let v = self.literal()?.to_string();
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    // Parses (|t| <integer>).
+    fn paren_integer(&mut self, t: Tok) -> Result<i32> {
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        let v = self.integer()?;
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    // Parses (|t| [on | off]).
+    fn paren_onoff(&mut self, t: Tok) -> Result<bool> {
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        let v = self.onoff()?;
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    // Parses (|t| <keyword>).
+    fn paren_keyword<T: FromStr>(&mut self, t: Tok, what: &str) -> Result<T> {
+        self.expect(Tok::Lparen)?;
+        self.expect(t)?;
+        let v = self.keyword(what)?;
+        self.expect(Tok::Rparen)?;
+        Ok(v)
+    }
+
+    // Consumes a parenthesised expression and returns it as text.
+    fn sexpr(&mut self) -> Result<String> {
+        self.expect(Tok::Lparen)?;
+        let mut s = String::from("(");
+        let mut depth = 1;
+        while depth > 0 {
+            let t = self.next()?;
+            if t.tok != Tok::Rparen && !s.ends_with('(') {
+                s.push(' ');
+            }
+            match t.tok {
+                Tok::Lparen => depth += 1,
+                Tok::Rparen => depth -= 1,
+                _ => {}
+            }
+            s.push_str(&t.s);
+        }
+        Ok(s)
+    }
+
+    fn keyword<T: FromStr>(&mut self, what: &str) -> Result<T> {
+        let s = self.literal()?.to_lowercase();
+        T::from_str(&s).map_err(|_| eyre!("unrecognised {} {}", what, s))
+    }
+
+    fn onoff(&mut self) -> Result<bool> {
+        match self.next()?.tok {
+            Tok::Off => Ok(false),
+            Tok::On => Ok(true),
+            _ => Err(eyre!("expected off or on")),
+        }
+    }
+
+    fn wire_type(&mut self) -> Result<DsnWireType> {
+        match self.next()?.tok {
+            Tok::Fix => Ok(DsnWireType::Fix),
+            Tok::Normal => Ok(DsnWireType::Normal),
+            Tok::Protect => Ok(DsnWireType::Protect),
+            Tok::Route => Ok(DsnWireType::Route),
+            _ => Err(eyre!("unrecognised wire type")),
+        }
+    }
+
+    fn object_type(&mut self) -> Result<DsnObjectType> {
+        match self.next()?.tok {
+            Tok::Area => Ok(DsnObjectType::Area),
+            Tok::Pin => Ok(DsnObjectType::Pin),
+            Tok::Smd => Ok(DsnObjectType::Smd),
+            Tok::Testpoint => Ok(DsnObjectType::TestPoint),
+            Tok::Via => Ok(DsnObjectType::Via),
+            Tok::Wire => Ok(DsnObjectType::Wire),
+            _ => Err(eyre!("unrecognised object type")),
+        }
+    }
+
+    fn side(&mut self) -> Result<DsnSide> {
+        match self.next()?.tok {
+            Tok::Back => Ok(DsnSide::Back),
+            Tok::Both => Ok(DsnSide::Both),
+            Tok::Front => Ok(DsnSide::Front),
+            _ => Err(eyre!("unrecognised side type")),
+        }
+    }
+
+    fn dimension(&mut self) -> Result<DsnDimensionUnit> {
+        Ok(match self.next()?.tok {
+            Tok::Inch => DsnDimensionUnit::Inch,
+            Tok::Mil => DsnDimensionUnit::Mil,
+            Tok::Cm => DsnDimensionUnit::Cm,
+            Tok::Mm => DsnDimensionUnit::Mm,
+            Tok::Um => DsnDimensionUnit::Um,
+            _ => return Err(eyre!("unknown dimension unit")),
+        })
+    }
+
+    fn exact_number(&mut self) -> Result<DsnNumber> {
+        DsnNumber::from_str(self.literal()?)
+    }
+
+    // Also records the number's exact form in |pcb.numbers|.
+    fn number(&mut self) -> Result<f64> {
+        self.next()?;
+        let s = &self.toks[self.idx - 1].s;
+        let v = DsnNumber::parse_f64(s)?;
+        if let Entry::Vacant(e) = self.pcb.numbers.entry(v.to_bits()) {
+            e.insert(DsnNumber::from_str(s)?);
+        }
+        Ok(v)
+    }
+
+    fn integer(&mut self) -> Result<i32> {
+        let n = self.exact_number()?;
+        n.to_i32().ok_or_else(|| eyre!("expected integer but got {}", n))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::types::{
+        DsnApertureType, DsnComponentStatus, DsnCostType, DsnDirection, DsnFlipStyle,
+        DsnFromToType, DsnGate, DsnGridDirection, DsnGridImageType, DsnGridType, DsnJunctionType,
+        DsnLimitType, DsnMirror, DsnNetType, DsnPermitOrient, DsnReal, DsnReorder, DsnShieldLoop,
+        DsnShieldType, DsnSupplyType, DsnWireAttr,
+    };
+
+    fn parse_dsn(data: &str) -> Result<DsnPcb> {
+        let lexer = Lexer::new(data)?;
+        let tokens = lexer.lex()?;
+        let parser = Parser::new(&tokens);
+        parser.parse()
+    }
+
+    #[test]
+    fn minimal_pcb() -> Result<()> {
+        let data = "(pcb test)";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.pcb_id, "test");
+        Ok(())
+    }
+
+    #[test]
+    fn pcb_with_resolution() -> Result<()> {
+        let data = "(pcb test (resolution mm 1000))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.pcb_id, "test");
+        assert_eq!(pcb.resolution.dimension, DsnDimensionUnit::Mm);
+        assert_eq!(pcb.resolution.amount, 1000);
+        Ok(())
+    }
+
+    #[test]
+    fn pcb_with_unit() -> Result<()> {
+        let data = "(pcb test (unit mm))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.unit.dimension, DsnDimensionUnit::Mm);
+        Ok(())
+    }
+
+    #[test]
+    fn library_with_padstack() -> Result<()> {
+        let data = "(pcb test (library (padstack pad1 (attach off))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.library.padstacks.len(), 1);
+        assert_eq!(pcb.library.padstacks[0].padstack_id, "pad1");
+        assert!(!pcb.library.padstacks[0].attach);
+        Ok(())
+    }
+
+    #[test]
+    fn library_with_padstack_attach_on() -> Result<()> {
+        let data = "(pcb test (library (padstack pad1 (attach on))))";
+        let pcb = parse_dsn(data)?;
+        assert!(pcb.library.padstacks[0].attach);
+        Ok(())
+    }
+
+    #[test]
+    fn library_with_image() -> Result<()> {
+        let data = "(pcb test (library (image img1)))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.library.images.len(), 1);
+        assert_eq!(pcb.library.images[0].image_id, "img1");
+        Ok(())
+    }
+
+    #[test]
+    fn image_with_pin() -> Result<()> {
+        let data = "(pcb test (library (image img1 (pin pad1 1 10.0 20.0))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.library.images[0].pins.len(), 1);
+        assert_eq!(pcb.library.images[0].pins[0].padstack_id, "pad1");
+        assert_eq!(pcb.library.images[0].pins[0].pin_id, "1");
+        Ok(())
+    }
+
+    #[test]
+    fn image_with_pin_rotation() -> Result<()> {
+        let data = "(pcb test (library (image img1 (pin pad1 (rotate 90.0) 1 10.0 20.0))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.library.images[0].pins[0].rotation, 90.0);
+        Ok(())
+    }
+
+    #[test]
+    fn network_with_net() -> Result<()> {
+        let data = "(pcb test (network (net net1)))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.network.nets.len(), 1);
+        assert_eq!(pcb.network.nets[0].net_id, "net1");
+        Ok(())
+    }
+
+    #[test]
+    fn net_with_pins() -> Result<()> {
+        let data = "(pcb test (network (net net1 (pins R1-1 R2-2))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.network.nets[0].pins.len(), 2);
+        assert_eq!(pcb.network.nets[0].pins[0].component_id, "R1");
+        assert_eq!(pcb.network.nets[0].pins[0].pin_id, "1");
+        assert_eq!(pcb.network.nets[0].pins[1].component_id, "R2");
+        assert_eq!(pcb.network.nets[0].pins[1].pin_id, "2");
+        Ok(())
+    }
+
+    #[test]
+    fn network_with_class() -> Result<()> {
+        let data = "(pcb test (network (class power GND VCC)))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.network.classes.len(), 1);
+        assert_eq!(pcb.network.classes[0].class_id, "power");
+        assert_eq!(pcb.network.classes[0].net_ids.len(), 2);
+        assert_eq!(pcb.network.classes[0].net_ids[0], "GND");
+        assert_eq!(pcb.network.classes[0].net_ids[1], "VCC");
+        Ok(())
+    }
+
+    #[test]
+    fn class_with_circuit() -> Result<()> {
+        let data = "(pcb test (network (class signal (circuit (use_via via1)))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.network.classes[0].circuits.len(), 1);
+        match &pcb.network.classes[0].circuits[0] {
+            DsnCircuit::UseVia(s) => assert_eq!(s, &vec![DsnUseVia::Padstack("via1".into())]),
+            _ => panic!("Expected use_via circuit"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn class_with_rule_width() -> Result<()> {
+        let data = "(pcb test (network (class signal (rule (width 0.5)))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.network.classes[0].rules.len(), 1);
+        match &pcb.network.classes[0].rules[0] {
+            DsnRule::Width(w) => assert_eq!(*w, 0.5),
+            _ => panic!("Expected width rule"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn class_with_rule_clearance() -> Result<()> {
+        let data = "(pcb test (network (class signal (rule (clearance 0.3)))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.network.classes[0].rules.len(), 1);
+        match &pcb.network.classes[0].rules[0] {
+            DsnRule::Clearance(c) => {
+                assert_eq!(c.amount, 0.3);
+                assert_eq!(c.types.len(), 1);
+                match c.types[0] {
+                    DsnClearanceType::All => (),
+                    _ => panic!("Expected All clearance type"),
+                }
+            }
+            _ => panic!("Expected clearance rule"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn clearance_with_type() -> Result<()> {
+        let data = "(pcb test (network (class signal (rule (clearance 0.3 (type smd_smd))))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.network.classes[0].rules[0] {
+            DsnRule::Clearance(c) => {
+                assert_eq!(c.types.len(), 1);
+                assert_eq!(
+                    c.types[0],
+                    DsnClearanceType::Pair(DsnObjectType::Smd, DsnObjectType::Smd)
+                );
+            }
+            _ => panic!("Expected clearance rule"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn placement_with_component() -> Result<()> {
+        let data = "(pcb test (placement (component img1)))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.placement.components.len(), 1);
+        assert_eq!(pcb.placement.components[0].image_id, "img1");
+        Ok(())
+    }
+
+    #[test]
+    fn component_with_place() -> Result<()> {
+        let data = "(pcb test (placement (component img1 (place R1 10.0 20.0 front 0.0))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.placement.components[0].refs.len(), 1);
+        assert_eq!(pcb.placement.components[0].refs[0].component_id, "R1");
+        assert_eq!(pcb.placement.components[0].refs[0].side, DsnSide::Front);
+        assert_eq!(pcb.placement.components[0].refs[0].rotation, 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn place_with_lock_type() -> Result<()> {
+        let data = "(pcb test (placement (component img1 (place R1 10.0 20.0 front 0.0 (lock_type position)))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.placement.components[0].refs[0].lock_types, vec![DsnLockType::Position]);
+        Ok(())
+    }
+
+    #[test]
+    fn place_with_part_number() -> Result<()> {
+        let data =
+            "(pcb test (placement (component img1 (place R1 10.0 20.0 front 0.0 (pn 1234)))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.placement.components[0].refs[0].part_number, "1234");
+        Ok(())
+    }
+
+    #[test]
+    fn layer_types() -> Result<()> {
+        let data = "(pcb test (structure (layer L1 (type signal)) (layer L2 (type power)) (layer L3 (type mixed)) (layer L4 (type jumper))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.structure.layers.len(), 4);
+        assert_eq!(pcb.structure.layers[0].layer_name, "L1");
+        assert_eq!(pcb.structure.layers[0].layer_type, DsnLayerType::Signal);
+        assert_eq!(pcb.structure.layers[1].layer_name, "L2");
+        assert_eq!(pcb.structure.layers[1].layer_type, DsnLayerType::Power);
+        assert_eq!(pcb.structure.layers[2].layer_name, "L3");
+        assert_eq!(pcb.structure.layers[2].layer_type, DsnLayerType::Mixed);
+        assert_eq!(pcb.structure.layers[3].layer_name, "L4");
+        assert_eq!(pcb.structure.layers[3].layer_type, DsnLayerType::Jumper);
+        Ok(())
+    }
+
+    #[test]
+    fn structure_with_via() -> Result<()> {
+        let data = "(pcb test (structure (via via1 via2)))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.structure.vias.len(), 2);
+        assert_eq!(pcb.structure.vias[0], "via1");
+        assert_eq!(pcb.structure.vias[1], "via2");
+        Ok(())
+    }
+
+    #[test]
+    fn rect_shape() -> Result<()> {
+        let data = "(pcb test (structure (boundary (rect Top 0 0 100 100))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.structure.boundaries[0] {
+            DsnShape::Rect(r) => {
+                assert_eq!(r.layer_id, "Top");
+            }
+            _ => panic!("Expected rect shape"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn circle_shape() -> Result<()> {
+        let data = "(pcb test (library (padstack pad1 (shape (circle Top 10.0)))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.library.padstacks[0].shapes[0].shape {
+            DsnShape::Circle(c) => {
+                assert_eq!(c.layer_id, "Top");
+                assert_eq!(c.diameter, 10.0);
+            }
+            _ => panic!("Expected circle shape"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn circle_shape_with_position() -> Result<()> {
+        let data = "(pcb test (library (padstack pad1 (shape (circle Top 10.0 5.0 5.0)))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.library.padstacks[0].shapes[0].shape {
+            DsnShape::Circle(c) => {
+                assert_eq!(c.p.x, 5.0);
+                assert_eq!(c.p.y, 5.0);
+            }
+            _ => panic!("Expected circle shape"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn polygon_shape() -> Result<()> {
+        let data = "(pcb test (structure (boundary (polygon Top 1.0 0 0 10 0 10 10 0 10))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.structure.boundaries[0] {
+            DsnShape::Polygon(p) => {
+                assert_eq!(p.layer_id, "Top");
+                assert_eq!(p.aperture_width, 1.0);
+                assert_eq!(p.pts.len(), 4);
+            }
+            _ => panic!("Expected polygon shape"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn path_shape() -> Result<()> {
+        let data = "(pcb test (structure (boundary (path Top 1.0 0 0 10 10 20 20))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.structure.boundaries[0] {
+            DsnShape::Path(p) => {
+                assert_eq!(p.layer_id, "Top");
+                assert_eq!(p.aperture_width, 1.0);
+                assert_eq!(p.pts.len(), 3);
+            }
+            _ => panic!("Expected path shape"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn qarc_shape() -> Result<()> {
+        let data = "(pcb test (structure (boundary (qarc Top 1.0 0 0 10 10 5 5))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.structure.boundaries[0] {
+            DsnShape::QArc(q) => {
+                assert_eq!(q.layer_id, "Top");
+                assert_eq!(q.aperture_width, 1.0);
+                assert_eq!(q.start.x, 0.0);
+                assert_eq!(q.end.x, 10.0);
+                assert_eq!(q.center.x, 5.0);
+            }
+            _ => panic!("Expected qarc shape"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn keepout() -> Result<()> {
+        let data = "(pcb test (structure (keepout (rect Top 0 0 10 10))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.structure.keepouts.len(), 1);
+        assert_eq!(pcb.structure.keepouts[0].keepout_type, DsnKeepoutType::Keepout);
+        Ok(())
+    }
+
+    #[test]
+    fn via_keepout() -> Result<()> {
+        let data = "(pcb test (structure (via_keepout (rect Top 0 0 10 10))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.structure.keepouts[0].keepout_type, DsnKeepoutType::ViaKeepout);
+        Ok(())
+    }
+
+    #[test]
+    fn wire_keepout() -> Result<()> {
+        let data = "(pcb test (structure (wire_keepout (rect Top 0 0 10 10))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.structure.keepouts[0].keepout_type, DsnKeepoutType::WireKeepout);
+        Ok(())
+    }
+
+    #[test]
+    fn image_with_keepout() -> Result<()> {
+        let data = "(pcb test (library (image img1 (keepout (rect Top 0 0 10 10)))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.library.images[0].keepouts.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn image_with_outline() -> Result<()> {
+        let data = "(pcb test (library (image img1 (outline (rect Top 0 0 10 10)))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.library.images[0].outlines.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn negative_numbers() -> Result<()> {
+        let data = "(pcb test (placement (component img1 (place R1 -10.5 -20.3 front 0.0))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.placement.components[0].refs[0].p, Some(pt(-10.5, -20.3)));
+        Ok(())
+    }
+
+    #[test]
+    fn decimal_numbers() -> Result<()> {
+        let data = "(pcb test (resolution mm 1000) (network (class signal (rule (width 0.254)))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.network.classes[0].rules[0] {
+            DsnRule::Width(w) => assert_eq!(*w, 0.254),
+            _ => panic!("Expected width rule"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_boundaries() -> Result<()> {
+        let data =
+            "(pcb test (structure (boundary (rect Top 0 0 100 100)) (boundary (circle Top 10))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.structure.boundaries.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_rules() -> Result<()> {
+        let data = "(pcb test (structure (rule (width 0.5) (clearance 0.3))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.structure.rules.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn padstack_with_multiple_shapes() -> Result<()> {
+        let data = r"
+            (pcb test (library (padstack pad1
+                (shape (circle Top 10.0))
+                (shape (circle Bottom 8.0))
+            )))
+        ";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.library.padstacks[0].shapes.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn error_empty_polygon() {
+        let data = "(pcb test (structure (boundary (polygon Top 1.0))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_polygon_insufficient_points() {
+        let data = "(pcb test (structure (boundary (polygon Top 1.0 0 0 10 10))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_empty_path() {
+        let data = "(pcb test (structure (boundary (path Top 1.0))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_path_insufficient_points() {
+        let data = "(pcb test (structure (boundary (path Top 1.0 0 0))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_on_unexpected_token() {
+        let data = "(pcb test (unknown_keyword))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_on_missing_rparen() {
+        let data = "(pcb test";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_on_unexpected_eof() {
+        let data = "(pcb test (library";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn side_variants() -> Result<()> {
+        let data_front = "(pcb test (placement (component img1 (place R1 0 0 front 0))))";
+        let data_back = "(pcb test (placement (component img1 (place R1 0 0 back 0))))";
+        let data_both = "(pcb test (placement (component img1 (place R1 0 0 both 0))))";
+
+        let pcb_front = parse_dsn(data_front)?;
+        let pcb_back = parse_dsn(data_back)?;
+        let pcb_both = parse_dsn(data_both)?;
+
+        assert_eq!(pcb_front.placement.components[0].refs[0].side, DsnSide::Front);
+        assert_eq!(pcb_back.placement.components[0].refs[0].side, DsnSide::Back);
+        assert_eq!(pcb_both.placement.components[0].refs[0].side, DsnSide::Both);
+        Ok(())
+    }
+
+    #[test]
+    fn lock_type_gate() -> Result<()> {
+        let data =
+            "(pcb test (placement (component img1 (place R1 0 0 front 0 (lock_type gate)))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.placement.components[0].refs[0].lock_types, vec![DsnLockType::Gate]);
+        Ok(())
+    }
+
+    #[test]
+    fn all_dimension_units() -> Result<()> {
+        let data_inch = "(pcb test (resolution inch 1000))";
+        let data_mil = "(pcb test (resolution mil 1000))";
+        let data_cm = "(pcb test (resolution cm 1000))";
+        let data_mm = "(pcb test (resolution mm 1000))";
+        let data_um = "(pcb test (resolution um 1000))";
+
+        assert_eq!(parse_dsn(data_inch)?.resolution.dimension, DsnDimensionUnit::Inch);
+        assert_eq!(parse_dsn(data_mil)?.resolution.dimension, DsnDimensionUnit::Mil);
+        assert_eq!(parse_dsn(data_cm)?.resolution.dimension, DsnDimensionUnit::Cm);
+        assert_eq!(parse_dsn(data_mm)?.resolution.dimension, DsnDimensionUnit::Mm);
+        assert_eq!(parse_dsn(data_um)?.resolution.dimension, DsnDimensionUnit::Um);
+        Ok(())
+    }
+
+    #[test]
+    fn parser_directive_ignored() -> Result<()> {
+        let data = "(pcb test (parser (host_cad freeroute)))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.pcb_id, "test");
+        Ok(())
+    }
+
+    #[test]
+    fn layer_with_property() -> Result<()> {
+        let data = "(pcb test (structure (layer Top (type signal) (property user_value 123))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.structure.layers[0].layer_name, "Top");
+        assert_eq!(
+            pcb.structure.layers[0].properties,
+            vec![DsnProperty { key: "user_value".into(), value: "123".into() }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn clearance_with_default_smd() -> Result<()> {
+        let data = "(pcb test (network (class signal (rule (clearance 0.3 (type default_smd))))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.network.classes[0].rules[0] {
+            DsnRule::Clearance(c) => {
+                assert_eq!(c.types.len(), 1);
+                match c.types[0] {
+                    DsnClearanceType::DefaultSmd => (),
+                    _ => panic!("Expected DefaultSmd clearance type"),
+                }
+            }
+            _ => panic!("Expected clearance rule"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn clearance_with_multiple_types() -> Result<()> {
+        let data = "(pcb test (network (class signal (rule (clearance 0.3 (type default_smd) (type smd_smd))))))";
+        let pcb = parse_dsn(data)?;
+        match &pcb.network.classes[0].rules[0] {
+            DsnRule::Clearance(c) => {
+                assert_eq!(c.types.len(), 2);
+            }
+            _ => panic!("Expected clearance rule"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn error_invalid_dimension_unit() {
+        let data = "(pcb test (resolution invalid 1000))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_invalid_layer_type() {
+        let data = "(pcb test (structure (layer Top (type invalid))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_invalid_side() {
+        let data = "(pcb test (placement (component img1 (place R1 0 0 invalid 0))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_invalid_number() {
+        let data = "(pcb test (resolution mm notanumber))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_missing_required_field() {
+        let data = "(pcb test (resolution))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn pin_reference_parsing() -> Result<()> {
+        let data = "(pcb test (network (net GND (pins R1-1 C2-2 U3-10 R-A-1 U-1-2))))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.network.nets[0].pins.len(), 5);
+        assert_eq!(pcb.network.nets[0].pins[2].component_id, "U3");
+        assert_eq!(pcb.network.nets[0].pins[2].pin_id, "10");
+        assert_eq!(pcb.network.nets[0].pins[3].component_id, "R-A");
+        assert_eq!(pcb.network.nets[0].pins[3].pin_id, "1");
+        assert_eq!(pcb.network.nets[0].pins[4].component_id, "U-1");
+        assert_eq!(pcb.network.nets[0].pins[4].pin_id, "2");
+        Ok(())
+    }
+
+    #[test]
+    fn error_invalid_pin_reference() {
+        let data = "(pcb test (network (net GND (pins R1_invalid))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn multiple_images_in_library() -> Result<()> {
+        let data = "(pcb test (library (image img1) (image img2) (image img3)))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.library.images.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_nets_in_network() -> Result<()> {
+        let data = "(pcb test (network (net GND) (net VCC) (net DATA)))";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.network.nets.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_sections() -> Result<()> {
+        let data = "(pcb test (library) (network) (placement) (wiring))";
+        let pcb = parse_dsn(data)?;
+        assert!(pcb.library.images.is_empty());
+        assert!(pcb.library.padstacks.is_empty());
+        assert!(pcb.network.nets.is_empty());
+        assert!(pcb.network.classes.is_empty());
+        assert!(pcb.placement.components.is_empty());
+        assert!(pcb.wiring.wires.is_empty());
+        assert!(pcb.wiring.vias.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn wiring_with_wire() -> Result<()> {
+        let data = r"
+            (pcb test (wiring
+                (wire (path F.Cu 0.25 0 0 10 0 10 10) (net GND) (turret 2) (type route)
+                    (attr fanout) (shield VCC) (supply))
+            ))
+        ";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(pcb.wiring.wires.len(), 1);
+        let wire = &pcb.wiring.wires[0];
+        match &wire.shape {
+            DsnShape::Path(p) => {
+                assert_eq!(p.layer_id, "F.Cu");
+                assert_eq!(p.pts.len(), 3);
+            }
+            _ => panic!("Expected path shape"),
+        }
+        assert_eq!(wire.net_id, "GND");
+        assert_eq!(wire.turret, Some(2));
+        assert_eq!(wire.wire_type, Some(DsnWireType::Route));
+        assert_eq!(wire.attr, Some(DsnWireAttr::Fanout));
+        assert_eq!(wire.shield_net_id, "VCC");
+        assert!(wire.supply);
+        Ok(())
     }
 
     #[test]
-    fn minimal_pcb() -> Result<()> {
-        let data = "(pcb test)";
+    fn wire_with_connect() -> Result<()> {
+        let data = r"
+            (pcb test (wiring
+                (wire (path Top 1 0 0 10 0)
+                    (connect (terminal pin U1-3) (terminal via)))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.pcb_id, "test");
+        let (a, b) = pcb.wiring.wires[0].connect.clone().ok_or_else(|| eyre!("no connect"))?;
+        assert_eq!(a.object_type, DsnObjectType::Pin);
+        assert_eq!(a.pin_ref, Some(DsnPinRef { component_id: "U1".into(), pin_id: "3".into() }));
+        assert_eq!(b.object_type, DsnObjectType::Via);
+        assert_eq!(b.pin_ref, None);
         Ok(())
     }
 
     #[test]
-    fn pcb_with_resolution() -> Result<()> {
-        let data = "(pcb test (resolution mm 1000))";
+    fn wire_with_window() -> Result<()> {
+        let data = r"
+            (pcb test (wiring
+                (wire (rect Top 0 0 10 10) (window (rect Top 2 2 4 4)) (type protect))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.pcb_id, "test");
-        assert_eq!(pcb.resolution.dimension, DsnDimensionUnit::Mm);
-        assert_eq!(pcb.resolution.amount, 1000);
+        let wire = &pcb.wiring.wires[0];
+        assert_eq!(wire.windows.len(), 1);
+        assert_eq!(wire.wire_type, Some(DsnWireType::Protect));
         Ok(())
     }
 
     #[test]
-    fn pcb_with_unit() -> Result<()> {
-        let data = "(pcb test (unit mm))";
-        let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.unit.dimension, DsnDimensionUnit::Mm);
-        Ok(())
+    fn error_wire_circle_shape() {
+        let data = "(pcb test (wiring (wire (circle Top 10))))";
+        assert!(parse_dsn(data).is_err());
     }
 
     #[test]
-    fn library_with_padstack() -> Result<()> {
-        let data = "(pcb test (library (padstack pad1 (attach off))))";
+    fn wiring_with_via() -> Result<()> {
+        let data = r"
+            (pcb test (wiring
+                (via via0 10 20 30 40 (net GND) (via_number 3) (type fix)
+                    (contact F.Cu In1.Cu) (supply))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.library.padstacks.len(), 1);
-        assert_eq!(pcb.library.padstacks[0].padstack_id, "pad1");
-        assert!(!pcb.library.padstacks[0].attach);
+        assert_eq!(pcb.wiring.vias.len(), 1);
+        let via = &pcb.wiring.vias[0];
+        assert_eq!(via.padstack_id, "via0");
+        assert_eq!(via.pts, vec![pt(10.0, 20.0), pt(30.0, 40.0)]);
+        assert_eq!(via.net_id, "GND");
+        assert_eq!(via.via_number, Some(3));
+        assert_eq!(via.via_type, Some(DsnWireType::Fix));
+        assert_eq!(via.contact_layers, vec!["F.Cu", "In1.Cu"]);
+        assert!(via.supply);
         Ok(())
     }
 
     #[test]
-    fn library_with_padstack_attach_on() -> Result<()> {
-        let data = "(pcb test (library (padstack pad1 (attach on))))";
+    fn via_attrs() -> Result<()> {
+        let data = r"
+            (pcb test (wiring
+                (via via0 0 0 (attr test))
+                (via via0 0 0 (attr virtual_pin VP1))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert!(pcb.library.padstacks[0].attach);
+        assert_eq!(pcb.wiring.vias[0].attr, Some(DsnViaAttr::Test));
+        assert_eq!(pcb.wiring.vias[1].attr, Some(DsnViaAttr::VirtualPin("VP1".to_string())));
         Ok(())
     }
 
     #[test]
-    fn library_with_image() -> Result<()> {
-        let data = "(pcb test (library (image img1)))";
+    fn wiring_with_virtual_pin() -> Result<()> {
+        let data = "(pcb test (wiring (virtual_pin VP1 5 6 (net CLK))))";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.library.images.len(), 1);
-        assert_eq!(pcb.library.images[0].image_id, "img1");
+        assert_eq!(pcb.wiring.virtual_pins.len(), 1);
+        assert_eq!(pcb.wiring.virtual_pins[0].name, "VP1");
+        assert_eq!(pcb.wiring.virtual_pins[0].p, pt(5.0, 6.0));
+        assert_eq!(pcb.wiring.virtual_pins[0].net_id, "CLK");
         Ok(())
     }
 
     #[test]
-    fn image_with_pin() -> Result<()> {
-        let data = "(pcb test (library (image img1 (pin pad1 1 10.0 20.0))))";
+    fn error_via_without_vertex() {
+        let data = "(pcb test (wiring (via via0 (net GND))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn structure_with_plane() -> Result<()> {
+        let data = r"
+            (pcb test (structure
+                (plane GND (polygon B.Cu 0 0 0 100 0 100 100 0 100)
+                    (window (rect B.Cu 10 10 20 20))
+                    (window (polygon B.Cu 0 50 50 60 50 60 60)))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.library.images[0].pins.len(), 1);
-        assert_eq!(pcb.library.images[0].pins[0].padstack_id, "pad1");
-        assert_eq!(pcb.library.images[0].pins[0].pin_id, "1");
+        assert_eq!(pcb.structure.planes.len(), 1);
+        let plane = &pcb.structure.planes[0];
+        assert_eq!(plane.net_id, "GND");
+        match &plane.shape {
+            DsnShape::Polygon(p) => assert_eq!(p.pts.len(), 4),
+            _ => panic!("Expected polygon shape"),
+        }
+        assert_eq!(plane.windows.len(), 2);
+        assert!(matches!(plane.windows[0], DsnWindow::Rect(_)));
+        assert!(matches!(plane.windows[1], DsnWindow::Polygon(_)));
         Ok(())
     }
 
     #[test]
-    fn image_with_pin_rotation() -> Result<()> {
-        let data = "(pcb test (library (image img1 (pin pad1 (rotate 90.0) 1 10.0 20.0))))";
+    fn error_plane_circle_window() {
+        let data =
+            "(pcb test (structure (plane GND (rect B.Cu 0 0 10 10) (window (circle B.Cu 2)))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn padstack_shape_with_window() -> Result<()> {
+        let data = r"
+            (pcb test (library (padstack pad1
+                (shape (rect Top -5 -5 5 5) (window (rect Top -1 -1 1 1)))
+            )))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.library.images[0].pins[0].rotation, 90.0);
+        let shape = &pcb.library.padstacks[0].shapes[0];
+        assert_eq!(shape.windows.len(), 1);
+        match &shape.windows[0] {
+            DsnWindow::Rect(r) => assert_eq!(r.rect, Rt::enclosing(pt(-1.0, -1.0), pt(1.0, 1.0))),
+            DsnWindow::Polygon(_) => panic!("Expected rect window"),
+        }
         Ok(())
     }
 
     #[test]
-    fn network_with_net() -> Result<()> {
-        let data = "(pcb test (network (net net1)))";
+    fn keepout_with_windows() -> Result<()> {
+        let data = r"
+            (pcb test (structure (keepout (rect Top 0 0 10 10)
+                (window (rect Top 1 1 2 2))
+                (window (polygon Top 0 5 5 6 5 6 6))
+            )))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.network.nets.len(), 1);
-        assert_eq!(pcb.network.nets[0].net_id, "net1");
+        assert_eq!(pcb.structure.keepouts[0].windows.len(), 2);
         Ok(())
     }
 
     #[test]
-    fn net_with_pins() -> Result<()> {
-        let data = "(pcb test (network (net net1 (pins R1-1 R2-2))))";
+    fn padstack_full() -> Result<()> {
+        let data = r"
+            (pcb test (library (padstack pad1 (unit mm)
+                (shape (circle Top 1.0) (reduced (circle Top 0.8)) (connect off))
+                (attach on (use_via via1))
+                (via_site 0.5 0.5)
+                (via_site -0.5 -0.5)
+                (rotate off)
+                (absolute on)
+                (rule (clearance 0.2))
+            )))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.network.nets[0].pins.len(), 2);
-        assert_eq!(pcb.network.nets[0].pins[0].component_id, "R1");
-        assert_eq!(pcb.network.nets[0].pins[0].pin_id, "1");
-        assert_eq!(pcb.network.nets[0].pins[1].component_id, "R2");
-        assert_eq!(pcb.network.nets[0].pins[1].pin_id, "2");
+        let padstack = &pcb.library.padstacks[0];
+        assert_eq!(padstack.unit, Some(DsnDimensionUnit::Mm));
+        assert!(padstack.attach);
+        assert_eq!(padstack.use_via, "via1");
+        assert_eq!(padstack.pad_via_sites, vec![pt(0.5, 0.5), pt(-0.5, -0.5)]);
+        assert!(!padstack.rotate);
+        assert!(padstack.absolute);
+        assert_eq!(padstack.rules.len(), 1);
+        let shape = &padstack.shapes[0];
+        assert!(!shape.connect);
+        match &shape.reduced {
+            Some(DsnShape::Circle(c)) => assert_eq!(c.diameter, 0.8),
+            _ => panic!("Expected reduced circle shape"),
+        }
         Ok(())
     }
 
     #[test]
-    fn network_with_class() -> Result<()> {
-        let data = "(pcb test (network (class power GND VCC)))";
+    fn padstack_defaults() -> Result<()> {
+        let data = "(pcb test (library (padstack pad1 (shape (circle Top 1.0)))))";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.network.classes.len(), 1);
-        assert_eq!(pcb.network.classes[0].class_id, "power");
-        assert_eq!(pcb.network.classes[0].net_ids.len(), 2);
-        assert_eq!(pcb.network.classes[0].net_ids[0], "GND");
-        assert_eq!(pcb.network.classes[0].net_ids[1], "VCC");
+        let padstack = &pcb.library.padstacks[0];
+        assert_eq!(padstack.unit, None);
+        assert!(padstack.use_via.is_empty());
+        assert!(padstack.rotate);
+        assert!(!padstack.absolute);
+        assert!(padstack.shapes[0].connect);
+        assert_eq!(padstack.shapes[0].reduced, None);
         Ok(())
     }
 
     #[test]
-    fn class_with_circuit() -> Result<()> {
-        let data = "(pcb test (network (class signal (circuit (use_via via1)))))";
+    fn image_full() -> Result<()> {
+        let data = r"
+            (pcb test (library (image NetTie
+                (side front)
+                (unit mm)
+                (pin pad1 1 0 0)
+                (conductor (path F.Cu 0.2 0 0 1 0))
+                (via via0 0.5 0 (net GND))
+                (rule (width 0.2))
+                (place_rule (spacing 0.5 (type smd_smd)) (permit_side off) (opposite_side on))
+                (property (height 1.2) (value 10k))
+            )))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.network.classes[0].circuits.len(), 1);
-        match &pcb.network.classes[0].circuits[0] {
-            DsnCircuit::UseVia(s) => assert_eq!(s, "via1"),
-        }
+        let image = &pcb.library.images[0];
+        assert_eq!(image.side, Some(DsnSide::Front));
+        assert_eq!(image.unit, Some(DsnDimensionUnit::Mm));
+        assert_eq!(image.pins.len(), 1);
+        assert_eq!(image.conductors.len(), 1);
+        assert_eq!(image.conductor_vias.len(), 1);
+        assert_eq!(image.conductor_vias[0].net_id, "GND");
+        assert_eq!(image.rules, vec![DsnRule::Width(0.2)]);
+        assert_eq!(
+            image.place_rules,
+            vec![
+                DsnPlaceRule::Spacing(DsnSpacing { amount: 0.5, spacing_type: "smd_smd".into() }),
+                DsnPlaceRule::PermitSide(None),
+                DsnPlaceRule::OppositeSide(true),
+            ]
+        );
+        assert_eq!(
+            image.properties,
+            vec![
+                DsnProperty { key: "height".into(), value: "1.2".into() },
+                DsnProperty { key: "value".into(), value: "10k".into() },
+            ]
+        );
         Ok(())
     }
 
     #[test]
-    fn class_with_rule_width() -> Result<()> {
-        let data = "(pcb test (network (class signal (rule (width 0.5)))))";
+    fn image_place_rule_permit_orient() -> Result<()> {
+        let data = "(pcb test (library (image img1 (place_rule (permit_orient horizontal)))))";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.network.classes[0].rules.len(), 1);
-        match &pcb.network.classes[0].rules[0] {
-            DsnRule::Width(w) => assert_eq!(*w, 0.5),
-            DsnRule::Clearance(_) => panic!("Expected width rule"),
-        }
+        assert_eq!(
+            pcb.library.images[0].place_rules,
+            vec![DsnPlaceRule::PermitOrient(DsnPermitOrient::Horizontal)]
+        );
         Ok(())
     }
 
     #[test]
-    fn class_with_rule_clearance() -> Result<()> {
-        let data = "(pcb test (network (class signal (rule (clearance 0.3)))))";
+    fn error_image_invalid_side() {
+        let data = "(pcb test (library (image img1 (side top))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn image_with_pin_array() -> Result<()> {
+        let data = r"
+            (pcb test (library (image Header
+                (pin pad1 1 0 0)
+                (pin pad2 (rotate 90) (array 2 3 2.54 0 2.54 0))
+                (pin pad2 (array 1 2 0 -1.27 0 0 B))
+            )))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.network.classes[0].rules.len(), 1);
-        match &pcb.network.classes[0].rules[0] {
-            DsnRule::Clearance(c) => {
-                assert_eq!(c.amount, 0.3);
-                assert_eq!(c.types.len(), 1);
-                match c.types[0] {
-                    DsnClearanceType::All => (),
-                    _ => panic!("Expected All clearance type"),
-                }
-            }
-            DsnRule::Width(_) => panic!("Expected clearance rule"),
-        }
+        let image = &pcb.library.images[0];
+        assert_eq!(image.pins.len(), 1);
+        assert_eq!(image.pin_arrays.len(), 2);
+        assert_eq!(image.pin_arrays[0].padstack_id, "pad2");
+        assert_eq!(image.pin_arrays[0].rotation, 90.0);
+        assert_eq!(image.pin_arrays[0].begin_index, 2);
+        assert_eq!(image.pin_arrays[0].count, 3);
+        assert_eq!(image.pin_arrays[1].prefix, "B");
+
+        let pins = image.expanded_pins()?;
+        assert_eq!(pins.len(), 6);
+        let ids: Vec<_> = pins.iter().map(|p| p.pin_id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "3", "4", "B1", "B2"]);
+        assert_eq!(pins[3].p, pt(7.62, 0.0));
+        assert_eq!(pins[3].rotation, 90.0);
+        assert_eq!(pins[5].p, pt(0.0, -1.27));
         Ok(())
     }
 
     #[test]
-    fn clearance_with_type() -> Result<()> {
-        let data = "(pcb test (network (class signal (rule (clearance 0.3 (type smd_smd))))))";
-        let pcb = parse_dsn(data)?;
-        match &pcb.network.classes[0].rules[0] {
-            DsnRule::Clearance(c) => {
-                assert_eq!(c.types.len(), 1);
-                match c.types[0] {
-                    DsnClearanceType::SmdSmd => (),
-                    _ => panic!("Expected SmdSmd clearance type"),
-                }
-            }
-            DsnRule::Width(_) => panic!("Expected clearance rule"),
-        }
+    fn error_pin_array_negative_count() {
+        let data = "(pcb test (library (image img1 (pin pad1 (array 1 -2 1 0 0 0)))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_pin_array_out_of_range() {
+        let overflow = "(pcb test (library (image img1 (pin pad1 (array 2147483647 2 1 0 0 0)))))";
+        assert!(parse_dsn(overflow).is_err());
+        let huge = "(pcb test (library (image img1 (pin pad1 (array 1 2000000000 1 0 0 0)))))";
+        assert!(parse_dsn(huge).is_err());
+    }
+
+    #[test]
+    fn pin_array_pins_checks_range() -> Result<()> {
+        let array = DsnPinArray { begin_index: i32::MAX, count: 2, ..Default::default() };
+        assert!(array.pins().is_err());
+        let array = DsnPinArray { begin_index: i32::MAX, count: 1, ..Default::default() };
+        assert_eq!(array.pins()?.len(), 1);
         Ok(())
     }
 
     #[test]
-    fn placement_with_component() -> Result<()> {
-        let data = "(pcb test (placement (component img1)))";
+    fn unplaced_component() -> Result<()> {
+        let data = "(pcb test (placement (component img1 (place R1) (place R2 (PN 10k)))))";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.placement.components.len(), 1);
-        assert_eq!(pcb.placement.components[0].image_id, "img1");
+        let refs = &pcb.placement.components[0].refs;
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].component_id, "R1");
+        assert_eq!(refs[0].p, None);
+        assert_eq!(refs[1].p, None);
+        assert_eq!(refs[1].part_number, "10k");
         Ok(())
     }
 
     #[test]
-    fn component_with_place() -> Result<()> {
-        let data = "(pcb test (placement (component img1 (place R1 10.0 20.0 front 0.0))))";
+    fn place_full() -> Result<()> {
+        let data = r"
+            (pcb test (placement
+                (place_control (flip_style rotate_first))
+                (component img1 (place U1 1 2 back 90
+                    (mirror x)
+                    (status added)
+                    (logical_part LP1)
+                    (place_rule (spacing 1.0))
+                    (property (height 3))
+                    (lock_type position gate subgate pin)
+                    (region R1 (rect Top 0 0 5 5) (class power) (rule (width 0.3)))
+                    (PN 74HC00)
+                ))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.placement.components[0].refs.len(), 1);
-        assert_eq!(pcb.placement.components[0].refs[0].component_id, "R1");
-        assert_eq!(pcb.placement.components[0].refs[0].side, DsnSide::Front);
-        assert_eq!(pcb.placement.components[0].refs[0].rotation, 0.0);
+        assert_eq!(pcb.placement.flip_style, Some(DsnFlipStyle::RotateFirst));
+        let r = &pcb.placement.components[0].refs[0];
+        assert_eq!(r.p, Some(pt(1.0, 2.0)));
+        assert_eq!(r.side, DsnSide::Back);
+        assert_eq!(r.rotation, 90.0);
+        assert_eq!(r.mirror, DsnMirror::X);
+        assert_eq!(r.status, Some(DsnComponentStatus::Added));
+        assert_eq!(r.logical_part, "LP1");
+        assert_eq!(r.place_rules.len(), 1);
+        assert_eq!(r.properties.len(), 1);
+        assert_eq!(
+            r.lock_types,
+            vec![DsnLockType::Position, DsnLockType::Gate, DsnLockType::Subgate, DsnLockType::Pin]
+        );
+        let region = r.region.as_ref().ok_or_else(|| eyre!("expected region"))?;
+        assert_eq!(region.region_id, "R1");
+        assert!(matches!(region.shape, Some(DsnShape::Rect(_))));
+        assert_eq!(region.class_id, "power");
+        assert_eq!(region.rules, vec![DsnRule::Width(0.3)]);
+        assert_eq!(r.part_number, "74HC00");
         Ok(())
     }
 
     #[test]
-    fn place_with_lock_type() -> Result<()> {
-        let data = "(pcb test (placement (component img1 (place R1 10.0 20.0 front 0.0 (lock_type position)))))";
+    fn error_invalid_mirror() {
+        let data = "(pcb test (placement (component img1 (place R1 0 0 front 0 (mirror z)))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn net_full() -> Result<()> {
+        let data = r"
+            (pcb test (network (net CLK
+                (unassigned)
+                (net_number 7)
+                (order U1-1 R1-1 U2-3)
+                (type fix)
+                (property (impedance 50))
+                (circuit (use_via via1))
+                (rule (width 0.15))
+                (layer_rule F.Cu B.Cu (rule (width 0.2)))
+                (fromto U1-1 R1-1 (type soft) (rule (width 0.1)))
+                (expose U1-1)
+                (noexpose U2-3)
+                (source U1-1)
+                (load U2-3 R1-1)
+                (terminator R1-1)
+                (supply ground)
+            )))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.placement.components[0].refs[0].lock_type, DsnLockType::Position);
+        let net = &pcb.network.nets[0];
+        assert!(net.unassigned);
+        assert_eq!(net.net_number, Some(7));
+        assert!(net.ordered);
+        let pins: Vec<_> = net.pins.iter().map(|p| p.component_id.as_str()).collect();
+        assert_eq!(pins, vec!["U1", "R1", "U2"]);
+        assert_eq!(net.net_type, Some(DsnNetType::Fix));
+        assert_eq!(net.properties.len(), 1);
+        assert_eq!(net.circuits.len(), 1);
+        assert_eq!(net.rules, vec![DsnRule::Width(0.15)]);
+        assert_eq!(net.layer_rules.len(), 1);
+        assert_eq!(net.layer_rules[0].layer_names, vec!["F.Cu", "B.Cu"]);
+        assert_eq!(net.layer_rules[0].rules, vec![DsnRule::Width(0.2)]);
+        assert_eq!(net.fromtos.len(), 1);
+        assert_eq!(net.fromtos[0].from, "U1-1");
+        assert_eq!(net.fromtos[0].to, "R1-1");
+        assert_eq!(net.fromtos[0].fromto_type, Some(DsnFromToType::Soft));
+        assert_eq!(net.expose.len(), 1);
+        assert_eq!(net.noexpose.len(), 1);
+        assert_eq!(net.source[0].component_id, "U1");
+        assert_eq!(net.load.len(), 2);
+        assert_eq!(net.terminator[0].component_id, "R1");
+        assert_eq!(net.supply, Some(DsnSupplyType::Ground));
         Ok(())
     }
 
     #[test]
-    fn place_with_part_number() -> Result<()> {
-        let data =
-            "(pcb test (placement (component img1 (place R1 10.0 20.0 front 0.0 (pn 1234)))))";
+    fn net_pins_not_ordered() -> Result<()> {
+        let data = "(pcb test (network (net GND (pins R1-1 R2-2) (supply power))))";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.placement.components[0].refs[0].part_number, "1234");
+        assert!(!pcb.network.nets[0].ordered);
+        assert_eq!(pcb.network.nets[0].supply, Some(DsnSupplyType::Power));
         Ok(())
     }
 
     #[test]
-    fn layer_types() -> Result<()> {
-        let data = "(pcb test (structure (layer L1 (type signal)) (layer L2 (type power)) (layer L3 (type mixed)) (layer L4 (type jumper))))";
+    fn error_invalid_net_type() {
+        let data = "(pcb test (network (net GND (type route))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_layer_rule_without_rule() {
+        let data = "(pcb test (network (net GND (layer_rule F.Cu))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn error_layer_rule_without_layers() {
+        let data = "(pcb test (network (net GND (layer_rule (rule (width 0.5))))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn network_groups_pairs_bundles() -> Result<()> {
+        let data = r"
+            (pcb test (network
+                (net USB_P) (net USB_N)
+                (class_class (classes power signal) (rule (clearance 0.5))
+                    (layer_rule F.Cu (rule (clearance 0.6))))
+                (group G1 (fromto U1-1 U2-1) (fromto U1-2 U2-2) (rule (width 0.2)))
+                (group_set GS1 G1 G2 (circuit (use_via via1)))
+                (pair (nets USB_P USB_N) (rule (width 0.1)) (layer_rule B.Cu (rule (width 0.12))))
+                (bundle DATA (nets D0 D1 D2) (gap 0.2) (gap 0.3 (layer F.Cu B.Cu)))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.structure.layers.len(), 4);
-        assert_eq!(pcb.structure.layers[0].layer_name, "L1");
-        assert_eq!(pcb.structure.layers[0].layer_type, DsnLayerType::Signal);
-        assert_eq!(pcb.structure.layers[1].layer_name, "L2");
-        assert_eq!(pcb.structure.layers[1].layer_type, DsnLayerType::Power);
-        assert_eq!(pcb.structure.layers[2].layer_name, "L3");
-        assert_eq!(pcb.structure.layers[2].layer_type, DsnLayerType::Mixed);
-        assert_eq!(pcb.structure.layers[3].layer_name, "L4");
-        assert_eq!(pcb.structure.layers[3].layer_type, DsnLayerType::Jumper);
+        let network = &pcb.network;
+        assert_eq!(network.class_classes.len(), 1);
+        assert_eq!(network.class_classes[0].class_ids, vec!["power", "signal"]);
+        assert_eq!(network.class_classes[0].rules.len(), 1);
+        assert_eq!(network.class_classes[0].layer_rules.len(), 1);
+        assert_eq!(network.groups.len(), 1);
+        assert_eq!(network.groups[0].group_id, "G1");
+        assert_eq!(network.groups[0].fromtos.len(), 2);
+        assert_eq!(network.groups[0].rules, vec![DsnRule::Width(0.2)]);
+        assert_eq!(network.group_sets[0].group_set_id, "GS1");
+        assert_eq!(network.group_sets[0].group_ids, vec!["G1", "G2"]);
+        assert_eq!(network.group_sets[0].circuits.len(), 1);
+        assert_eq!(network.pairs.len(), 1);
+        assert_eq!(network.pairs[0].net_ids, ("USB_P".to_string(), "USB_N".to_string()));
+        assert_eq!(network.pairs[0].rules, vec![DsnRule::Width(0.1)]);
+        assert_eq!(network.pairs[0].layer_rules[0].layer_names, vec!["B.Cu"]);
+        assert_eq!(network.bundles[0].bundle_id, "DATA");
+        assert_eq!(network.bundles[0].net_ids, vec!["D0", "D1", "D2"]);
+        assert_eq!(
+            network.bundles[0].gaps,
+            vec![
+                DsnBundleGap { amount: 0.2, layer_ids: vec![] },
+                DsnBundleGap { amount: 0.3, layer_ids: vec!["F.Cu".into(), "B.Cu".into()] },
+            ]
+        );
         Ok(())
     }
 
     #[test]
-    fn structure_with_via() -> Result<()> {
-        let data = "(pcb test (structure (via via1 via2)))";
+    fn error_pair_single_net() {
+        let data = "(pcb test (network (pair (nets USB_P))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn rule_descriptors() -> Result<()> {
+        let data = r"
+            (pcb test (structure (rule
+                (effective_via_length 1.5)
+                (inter_layer_clearance 0.2 (layer_pair F.Cu In1.Cu))
+                (junction_type term_only)
+                (length_amplitude 0.5 2)
+                (limit_vias 4)
+                (max_total_vias -1)
+                (max_stub 1.25)
+                (parallel_noise (gap 0.3) (threshold 2) (weight 0.5) (layer_pair F.Cu F.Cu))
+                (tandem_segment (gap 0.4) (limit 10))
+                (reorder daisy)
+                (shield_loop closed)
+                (stack_via via1 via2)
+                (stack_via_depth 3 (layer_pair F.Cu B.Cu))
+                (testpoint_rule (side back) (center_center 2.5) (pin_allow off))
+                (tjunction on)
+                (track_id T1)
+                (via_at_smd on (grid off) (fit on))
+            )))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.structure.vias.len(), 2);
-        assert_eq!(pcb.structure.vias[0], "via1");
-        assert_eq!(pcb.structure.vias[1], "via2");
+        let layer_pair = Some(("F.Cu".to_string(), "B.Cu".to_string()));
+        assert_eq!(
+            pcb.structure.rules,
+            vec![
+                DsnRule::EffectiveViaLength(1.5),
+                DsnRule::InterLayerClearance(DsnInterLayerClearance {
+                    amount: 0.2,
+                    layer_pairs: vec![("F.Cu".into(), "In1.Cu".into())],
+                }),
+                DsnRule::JunctionType(DsnJunctionType::TermOnly),
+                DsnRule::LengthAmplitude(0.5, 2.0),
+                DsnRule::LimitVias(4),
+                DsnRule::MaxTotalVias(-1),
+                DsnRule::MaxStub(1.25),
+                DsnRule::ParallelNoise(DsnNoise {
+                    gap: Some(0.3),
+                    threshold: Some(2.0),
+                    weight: Some(0.5),
+                    layer_pair: Some(("F.Cu".into(), "F.Cu".into())),
+                }),
+                DsnRule::TandemSegment(DsnSegment {
+                    gap: Some(0.4),
+                    limit: Some(10.0),
+                    layer_pair: None,
+                }),
+                DsnRule::Reorder(DsnReorder::Daisy),
+                DsnRule::ShieldLoop(DsnShieldLoop::Closed),
+                DsnRule::StackVia(vec!["via1".into(), "via2".into()]),
+                DsnRule::StackViaDepth(DsnStackViaDepth { depth: 3, layer_pair }),
+                DsnRule::TestpointRule(DsnTestpointRule {
+                    side: Some(DsnSide::Back),
+                    center_center: Some(2.5),
+                    comp_edge_center: None,
+                    pin_allow: Some(false),
+                }),
+                DsnRule::Tjunction(true),
+                DsnRule::TrackId("T1".into()),
+                DsnRule::ViaAtSmd(DsnViaAtSmd {
+                    allowed: true,
+                    grid: Some(false),
+                    fit: Some(true)
+                }),
+            ]
+        );
         Ok(())
     }
 
     #[test]
-    fn rect_shape() -> Result<()> {
-        let data = "(pcb test (structure (boundary (rect Top 0 0 100 100))))";
+    fn unknown_rule_kept() -> Result<()> {
+        let data = "(pcb test (network (class c1 (rule (width 0.2) (vendor_rule 1 (sub a b)) (clearance 0.1)))))";
         let pcb = parse_dsn(data)?;
-        match &pcb.structure.boundaries[0] {
-            DsnShape::Rect(r) => {
-                assert_eq!(r.layer_id, "Top");
-            }
-            _ => panic!("Expected rect shape"),
-        }
+        let rules = &pcb.network.classes[0].rules;
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[1], DsnRule::Unknown("(vendor_rule 1 (sub a b))".to_string()));
+        assert!(matches!(rules[2], DsnRule::Clearance(_)));
         Ok(())
     }
 
     #[test]
-    fn circle_shape() -> Result<()> {
-        let data = "(pcb test (library (padstack pad1 (shape (circle Top 10.0)))))";
+    fn error_invalid_rule_value() {
+        let data = "(pcb test (structure (rule (limit_vias many))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn clearance_type_pairs() -> Result<()> {
+        let data = r"
+            (pcb test (structure (rule
+                (clearance 0.2 (type wire_via smd_pin testpoint_area))
+                (clearance 0.3 (type smd_via_same_net via_via_same_net antipad_gap))
+                (clearance 0.4 (type buried_via_gap (layer_depth 2) pad_to_turn_gap))
+                (clearance 0.5 (type buried_via_gap) (type smd_to_turn_gap))
+            )))
+        ";
         let pcb = parse_dsn(data)?;
-        match &pcb.library.padstacks[0].shapes[0].shape {
-            DsnShape::Circle(c) => {
-                assert_eq!(c.layer_id, "Top");
-                assert_eq!(c.diameter, 10.0);
-            }
-            _ => panic!("Expected circle shape"),
-        }
+        let types: Vec<_> = pcb
+            .structure
+            .rules
+            .iter()
+            .map(|r| match r {
+                DsnRule::Clearance(c) => c.types.clone(),
+                _ => panic!("Expected clearance rule"),
+            })
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                vec![
+                    DsnClearanceType::Pair(DsnObjectType::Wire, DsnObjectType::Via),
+                    DsnClearanceType::Pair(DsnObjectType::Smd, DsnObjectType::Pin),
+                    DsnClearanceType::Pair(DsnObjectType::TestPoint, DsnObjectType::Area),
+                ],
+                vec![
+                    DsnClearanceType::SmdViaSameNet,
+                    DsnClearanceType::ViaViaSameNet,
+                    DsnClearanceType::AntipadGap,
+                ],
+                vec![DsnClearanceType::BuriedViaGap(Some(2)), DsnClearanceType::PadToTurnGap],
+                vec![DsnClearanceType::BuriedViaGap(None), DsnClearanceType::SmdToTurnGap],
+            ]
+        );
         Ok(())
     }
 
     #[test]
-    fn circle_shape_with_position() -> Result<()> {
-        let data = "(pcb test (library (padstack pad1 (shape (circle Top 10.0 5.0 5.0)))))";
+    fn error_invalid_clearance_type() {
+        let data = "(pcb test (structure (rule (clearance 0.2 (type wire_board)))))";
+        assert!(parse_dsn(data).is_err());
+        let data = "(pcb test (structure (rule (clearance 0.2 (type smd)))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn layer_full() -> Result<()> {
+        let data = r"
+            (pcb test (structure
+                (layer F.Cu (type signal) (property (index 0))
+                    (direction horizontal) (rule (width 0.2)) (cost high (type way)))
+                (layer In1.Cu (type power) (direction positive_diagonal) (cost -1)
+                    (use_net GND AGND))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        match &pcb.library.padstacks[0].shapes[0].shape {
-            DsnShape::Circle(c) => {
-                assert_eq!(c.p.x, 5.0);
-                assert_eq!(c.p.y, 5.0);
-            }
-            _ => panic!("Expected circle shape"),
-        }
+        let top = &pcb.structure.layers[0];
+        assert_eq!(top.properties, vec![DsnProperty { key: "index".into(), value: "0".into() }]);
+        assert_eq!(top.direction, Some(DsnDirection::Horizontal));
+        assert_eq!(top.rules, vec![DsnRule::Width(0.2)]);
+        assert_eq!(
+            top.cost,
+            Some(DsnLayerCost { cost: DsnCost::High, cost_type: Some(DsnCostType::Way) })
+        );
+        let inner = &pcb.structure.layers[1];
+        assert_eq!(inner.direction, Some(DsnDirection::PositiveDiagonal));
+        assert_eq!(inner.cost, Some(DsnLayerCost { cost: DsnCost::Value(-1), cost_type: None }));
+        assert_eq!(inner.use_net, vec!["GND", "AGND"]);
         Ok(())
     }
 
     #[test]
-    fn polygon_shape() -> Result<()> {
-        let data = "(pcb test (structure (boundary (polygon Top 1.0 0 0 10 0 10 10 0 10))))";
-        let pcb = parse_dsn(data)?;
-        match &pcb.structure.boundaries[0] {
-            DsnShape::Polygon(p) => {
-                assert_eq!(p.layer_id, "Top");
-                assert_eq!(p.aperture_width, 1.0);
-                assert_eq!(p.pts.len(), 4);
-            }
-            _ => panic!("Expected polygon shape"),
-        }
+    fn error_invalid_layer_direction() {
+        let data = "(pcb test (structure (layer Top (type signal) (direction sideways))))";
+        assert!(parse_dsn(data).is_err());
+    }
+
+    #[test]
+    fn structure_control_and_grid() -> Result<()> {
+        let data = r"
+            (pcb test (structure
+                (control (off_grid on) (via_at_smd off) (noise_calculation linear_interpolation))
+                (grid via 0.1)
+                (grid wire 0.05 (direction x) (offset 0.025))
+                (grid place 1.27 (image_type smd))
+            ))
+        ";
+        let pcb = parse_dsn(data)?;
+        let control = pcb.structure.control.as_ref().ok_or_else(|| eyre!("missing control"))?;
+        assert_eq!(control.off_grid, Some(true));
+        let via_at_smd = control.via_at_smd.as_ref().ok_or_else(|| eyre!("missing via_at_smd"))?;
+        assert!(!via_at_smd.allowed);
+        assert_eq!(control.unknown, vec!["(noise_calculation linear_interpolation)"]);
+        let grids = &pcb.structure.grids;
+        assert_eq!(grids.len(), 3);
+        assert_eq!(grids[0].grid_type, DsnGridType::Via);
+        assert_eq!(grids[0].amount, 0.1);
+        assert_eq!(grids[1].direction, Some(DsnGridDirection::X));
+        assert_eq!(grids[1].offset, Some(0.025));
+        assert_eq!(grids[2].grid_type, DsnGridType::Place);
+        assert_eq!(grids[2].image_type, Some(DsnGridImageType::Smd));
         Ok(())
     }
 
     #[test]
-    fn path_shape() -> Result<()> {
-        let data = "(pcb test (structure (boundary (path Top 1.0 0 0 10 10 20 20))))";
+    fn structure_place_boundary_region_and_noise_weight() -> Result<()> {
+        let data = r"
+            (pcb test (structure
+                (layer_noise_weight (layer_pair Top Bottom 0.5) (layer_pair Top In1 1))
+                (place_boundary (rect pcb 0 0 100 100))
+                (region r1 (rect Top 0 0 10 10) (net GND) (rule (width 0.5)))
+                (place_rule (spacing 2.5))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        match &pcb.structure.boundaries[0] {
-            DsnShape::Path(p) => {
-                assert_eq!(p.layer_id, "Top");
-                assert_eq!(p.aperture_width, 1.0);
-                assert_eq!(p.pts.len(), 3);
-            }
-            _ => panic!("Expected path shape"),
-        }
+        let s = &pcb.structure;
+        assert_eq!(s.layer_noise_weights.len(), 2);
+        assert_eq!(s.layer_noise_weights[0].layer_pair, ("Top".into(), "Bottom".into()));
+        assert_eq!(s.layer_noise_weights[0].weight, 0.5);
+        assert_eq!(s.place_boundary.len(), 1);
+        assert!(matches!(s.place_boundary[0], DsnShape::Rect(_)));
+        assert_eq!(s.regions[0].region_id, "r1");
+        assert_eq!(s.regions[0].net_id, "GND");
+        assert_eq!(s.regions[0].rules, vec![DsnRule::Width(0.5)]);
+        assert_eq!(s.place_rules.len(), 1);
         Ok(())
     }
 
     #[test]
-    fn qarc_shape() -> Result<()> {
-        let data = "(pcb test (structure (boundary (qarc Top 1.0 0 0 10 10 5 5))))";
-        let pcb = parse_dsn(data)?;
-        match &pcb.structure.boundaries[0] {
-            DsnShape::QArc(q) => {
-                assert_eq!(q.layer_id, "Top");
-                assert_eq!(q.aperture_width, 1.0);
-                assert_eq!(q.start.x, 0.0);
-                assert_eq!(q.end.x, 10.0);
-                assert_eq!(q.center.x, 5.0);
-            }
-            _ => panic!("Expected qarc shape"),
-        }
-        Ok(())
+    fn error_invalid_grid_type() {
+        let data = "(pcb test (structure (grid diagonal 0.1)))";
+        assert!(parse_dsn(data).is_err());
     }
 
     #[test]
-    fn keepout() -> Result<()> {
-        let data = "(pcb test (structure (keepout (rect Top 0 0 10 10))))";
+    fn circuit_length_and_delay() -> Result<()> {
+        let data = r"
+            (pcb test (network (class ddr D0 D1 (circuit
+                (length 50 40 (type actual))
+                (total_delay 2.5)
+                (match_net_length on 0.5)
+                (match_group_delay (type ratio))
+                (relative_length D0_D1 1.5 0.1)
+                (sample_window 1 2)
+                (max_restricted_layer_length 10)
+                (priority 3)
+            ))))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.structure.keepouts.len(), 1);
-        assert_eq!(pcb.structure.keepouts[0].keepout_type, DsnKeepoutType::Keepout);
+        let c = &pcb.network.classes[0].circuits;
+        assert_eq!(c.len(), 8);
+        assert_eq!(
+            c[0],
+            DsnCircuit::Length(DsnLimit {
+                max: 50.0,
+                min: Some(40.0),
+                limit_type: Some(DsnLimitType::Actual)
+            })
+        );
+        assert_eq!(c[1], DsnCircuit::TotalDelay(DsnLimit { max: 2.5, ..Default::default() }));
+        assert_eq!(
+            c[2],
+            DsnCircuit::MatchNetLength(DsnMatch {
+                on: true,
+                tolerance: Some(0.5),
+                match_type: None
+            })
+        );
+        assert_eq!(
+            c[3],
+            DsnCircuit::MatchGroupDelay(DsnMatch {
+                match_type: Some(DsnLimitType::Ratio),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            c[4],
+            DsnCircuit::RelativeLength(DsnRelative {
+                reference_id: "D0_D1".into(),
+                delta: 1.5,
+                tolerance: 0.1
+            })
+        );
+        assert_eq!(c[5], DsnCircuit::SampleWindow(DsnTimeWindow { begin: 1.0, end: 2.0 }));
+        assert_eq!(c[6], DsnCircuit::MaxRestrictedLayerLength(10.0));
+        assert_eq!(c[7], DsnCircuit::Priority(3));
         Ok(())
     }
 
     #[test]
-    fn via_keepout() -> Result<()> {
-        let data = "(pcb test (structure (via_keepout (rect Top 0 0 10 10))))";
+    fn circuit_shield_use_layer_and_use_via() -> Result<()> {
+        let data = r"
+            (pcb test (network (class clk CLK (circuit
+                (shield on (type coax) (use_net GND))
+                (use_layer In1.Cu In2.Cu)
+                (use_via via1 (use_array arr1 2 3) (use_array arr2))
+            ))))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.structure.keepouts[0].keepout_type, DsnKeepoutType::ViaKeepout);
+        let c = &pcb.network.classes[0].circuits;
+        assert_eq!(
+            c[0],
+            DsnCircuit::Shield(DsnShield {
+                on: true,
+                shield_type: Some(DsnShieldType::Coax),
+                net_id: "GND".into()
+            })
+        );
+        assert_eq!(c[1], DsnCircuit::UseLayer(vec!["In1.Cu".into(), "In2.Cu".into()]));
+        assert_eq!(
+            c[2],
+            DsnCircuit::UseVia(vec![
+                DsnUseVia::Padstack("via1".into()),
+                DsnUseVia::Array { template_id: "arr1".into(), rows: Some(2), columns: Some(3) },
+                DsnUseVia::Array { template_id: "arr2".into(), rows: None, columns: None },
+            ])
+        );
         Ok(())
     }
 
     #[test]
-    fn wire_keepout() -> Result<()> {
-        let data = "(pcb test (structure (wire_keepout (rect Top 0 0 10 10))))";
+    fn class_with_composite() -> Result<()> {
+        let data = r"
+            (pcb test (network (class bus CLK (composite (prefix DATA) 0 3)
+                (composite (prefix A) 6 2 2 (suffix _N))
+                (layer_rule Top (rule (width 0.1))))))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.structure.keepouts[0].keepout_type, DsnKeepoutType::WireKeepout);
+        let class = &pcb.network.classes[0];
+        assert_eq!(
+            class.net_ids,
+            vec!["CLK", "DATA0", "DATA1", "DATA2", "DATA3", "A6_N", "A4_N", "A2_N"]
+        );
+        assert_eq!(class.composites.len(), 2);
+        assert_eq!(class.composites[1].step, 2);
+        assert_eq!(class.layer_rules[0].layer_names, vec!["Top"]);
         Ok(())
     }
 
     #[test]
-    fn image_with_keepout() -> Result<()> {
-        let data = "(pcb test (library (image img1 (keepout (rect Top 0 0 10 10)))))";
+    fn class_with_topology() -> Result<()> {
+        let data = r"
+            (pcb test (network (class ddr (topology
+                (fromto U1-1 U2-1 (type fix))
+                (composite (prefix ADDR) 0 1)
+            ))))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.library.images[0].keepouts.len(), 1);
+        let topology =
+            pcb.network.classes[0].topology.as_ref().ok_or_else(|| eyre!("missing topology"))?;
+        assert_eq!(topology.fromtos.len(), 1);
+        assert_eq!(topology.fromtos[0].from, "U1-1");
+        assert_eq!(topology.composites[0].names()?, vec!["ADDR0", "ADDR1"]);
+        assert!(pcb.network.classes[0].net_ids.is_empty());
         Ok(())
     }
 
     #[test]
-    fn image_with_outline() -> Result<()> {
-        let data = "(pcb test (library (image img1 (outline (rect Top 0 0 10 10)))))";
-        let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.library.images[0].outlines.len(), 1);
-        Ok(())
+    fn error_composite_bad_step() {
+        let data = "(pcb test (network (class bus (composite (prefix D) 0 3 0))))";
+        assert!(parse_dsn(data).is_err());
+        let data = "(pcb test (network (class bus (composite (prefix D) 0 3 -1))))";
+        assert!(parse_dsn(data).is_err());
     }
 
     #[test]
-    fn negative_numbers() -> Result<()> {
-        let data = "(pcb test (placement (component img1 (place R1 -10.5 -20.3 front 0.0))))";
-        let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.placement.components[0].refs[0].p.x, -10.5);
-        assert_eq!(pcb.placement.components[0].refs[0].p.y, -20.3);
+    fn composite_extreme_range() -> Result<()> {
+        let data = "(pcb test (network (class bus (composite 2147483646 -2147483648))))";
+        assert!(parse_dsn(data).is_err());
+        let c = DsnCompositeNameList {
+            begin_index: i32::MAX,
+            end_index: i32::MAX - 4,
+            step: 2,
+            ..Default::default()
+        };
+        assert_eq!(c.names()?, vec!["2147483647", "2147483645", "2147483643"]);
         Ok(())
     }
 
     #[test]
-    fn decimal_numbers() -> Result<()> {
-        let data = "(pcb test (resolution mm 1000) (network (class signal (rule (width 0.254)))))";
+    fn library_with_jumper_and_via_array_template() -> Result<()> {
+        let data = r"
+            (pcb test (library
+                (jumper (length 5.08) (use_via via1) (height 1.2))
+                (via_array_template arr1 2 3 0.5 0.6 (use_via via1))
+                (directory /lib/images)
+                (extra_image_directory /lib/extra)
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        match &pcb.network.classes[0].rules[0] {
-            DsnRule::Width(w) => assert_eq!(*w, 0.254),
-            DsnRule::Clearance(_) => panic!("Expected width rule"),
-        }
+        let lib = &pcb.library;
+        assert_eq!(
+            lib.jumpers,
+            vec![DsnJumper { length: 5.08, use_via: "via1".into(), height: Some(1.2) }]
+        );
+        let t = &lib.via_array_templates[0];
+        assert_eq!(t.template_id, "arr1");
+        assert_eq!((t.rows, t.columns), (2, 3));
+        assert_eq!((t.row_spacing, t.column_spacing), (0.5, 0.6));
+        assert_eq!(t.use_via, "via1");
+        assert_eq!(lib.directory.as_deref(), Some("/lib/images"));
+        assert_eq!(lib.extra_image_directory.as_deref(), Some("/lib/extra"));
         Ok(())
     }
 
     #[test]
-    fn multiple_boundaries() -> Result<()> {
-        let data =
-            "(pcb test (structure (boundary (rect Top 0 0 100 100)) (boundary (circle Top 10))))";
+    fn library_with_family_family_and_image_image() -> Result<()> {
+        let data = r"
+            (pcb test (library
+                (family_family (family caps) (family resistors) (place_rule (spacing 1)))
+                (image_image (image SOT23) (image SOIC8) (rule (clearance 0.2)))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.structure.boundaries.len(), 2);
+        let ff = &pcb.library.family_families[0];
+        assert_eq!(ff.family_ids, ("caps".into(), "resistors".into()));
+        assert_eq!(ff.place_rules.len(), 1);
+        let ii = &pcb.library.image_images[0];
+        assert_eq!(ii.image_ids, ("SOT23".into(), "SOIC8".into()));
+        assert_eq!(ii.rules.len(), 1);
         Ok(())
     }
 
     #[test]
-    fn multiple_rules() -> Result<()> {
-        let data = "(pcb test (structure (rule (width 0.5) (clearance 0.3))))";
+    fn library_jumper_and_template_without_use_via() -> Result<()> {
+        let data =
+            "(pcb test (library (jumper (length 2.54)) (via_array_template arr1 1 2 0.5 0.5)))";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.structure.rules.len(), 2);
+        assert!(pcb.library.jumpers[0].use_via.is_empty());
+        assert!(pcb.library.via_array_templates[0].use_via.is_empty());
         Ok(())
     }
 
     #[test]
-    fn padstack_with_multiple_shapes() -> Result<()> {
+    fn floor_plan_with_clusters_and_rooms() -> Result<()> {
         let data = r"
-            (pcb test (library (padstack pad1
-                (shape (circle Top 10.0))
-                (shape (circle Bottom 8.0))
-            )))
+            (pcb test (floor_plan
+                (cluster analog (comp U1 U2 R1) (place_rule (spacing 0.5)))
+                (room analog_room (rect Top 0 0 50 50) (side front) (include analog)
+                    (exclude U9))
+                (room digital_room (polygon Top 0 50 0 100 0 100 50) (include U3 U4))
+            ))
         ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.library.padstacks[0].shapes.len(), 2);
+        let fp = &pcb.floor_plan;
+        assert_eq!(fp.clusters.len(), 1);
+        assert_eq!(fp.clusters[0].cluster_id, "analog");
+        assert_eq!(fp.clusters[0].component_ids, vec!["U1", "U2", "R1"]);
+        assert_eq!(fp.clusters[0].place_rules.len(), 1);
+        assert_eq!(fp.rooms.len(), 2);
+        let analog = &fp.rooms[0];
+        assert_eq!(analog.room_id, "analog_room");
+        assert!(matches!(analog.shapes[0], DsnShape::Rect(_)));
+        assert_eq!(analog.side, Some(DsnSide::Front));
+        assert_eq!(analog.include, vec!["analog"]);
+        assert_eq!(analog.exclude, vec!["U9"]);
+        assert!(matches!(fp.rooms[1].shapes[0], DsnShape::Polygon(_)));
+        assert_eq!(fp.rooms[1].include, vec!["U3", "U4"]);
         Ok(())
     }
 
     #[test]
-    fn error_empty_polygon() {
-        let data = "(pcb test (structure (boundary (polygon Top 1.0))))";
+    fn error_floor_plan_unknown_token() {
+        let data = "(pcb test (floor_plan (room r1 (circle Top 5))))";
         assert!(parse_dsn(data).is_err());
     }
 
     #[test]
-    fn error_polygon_insufficient_points() {
-        let data = "(pcb test (structure (boundary (polygon Top 1.0 0 0 10 10))))";
-        assert!(parse_dsn(data).is_err());
+    fn part_library_mappings() -> Result<()> {
+        let data = r"
+            (pcb test (part_library
+                (physical_part_mapping SOIC14 (component U1 U2))
+                (logical_part_mapping 7400 (component U1 U2))
+            ))
+        ";
+        let pcb = parse_dsn(data)?;
+        let lib = &pcb.part_library;
+        assert_eq!(lib.physical_part_mappings[0].part_id, "SOIC14");
+        assert_eq!(lib.physical_part_mappings[0].component_ids, vec!["U1", "U2"]);
+        assert_eq!(lib.logical_part_mappings[0].part_id, "7400");
+        assert_eq!(lib.logical_part_mappings[0].component_ids, vec!["U1", "U2"]);
+        Ok(())
     }
 
     #[test]
-    fn error_empty_path() {
-        let data = "(pcb test (structure (boundary (path Top 1.0))))";
-        assert!(parse_dsn(data).is_err());
+    fn part_library_logical_part_gates() -> Result<()> {
+        let data = r"
+            (pcb test (part_library (logical_part 7400
+                (pin 1 A 1 in1 1 2 A 1 in2 1 3 A 1 out 0)
+                (pin 4 B 1 in1 1 (subgate S1 2 a 0))
+                (pin 5 B 1 in2 1 6 B 1 out 0)
+                (pin 7 PWR 0 gnd 0)
+            )))
+        ";
+        let pcb = parse_dsn(data)?;
+        let lp = &pcb.part_library.logical_parts[0];
+        assert_eq!(lp.logical_part_id, "7400");
+        assert_eq!(lp.pins.len(), 7);
+        assert_eq!(
+            lp.pins[3].subgate,
+            Some(DsnSubgatePin {
+                subgate_id: "S1".into(),
+                subgate_swap_code: 2,
+                subgate_pin_id: "a".into(),
+                subgate_pin_swap_code: 0,
+            })
+        );
+        let gates = lp.gates();
+        assert_eq!(gates.len(), 3);
+        assert_eq!(
+            gates[1],
+            DsnGate {
+                gate_id: "B".into(),
+                swap_code: 1,
+                pin_ids: vec!["4".into(), "5".into(), "6".into()]
+            }
+        );
+        assert_eq!(gates[2].swap_code, 0);
+        assert_eq!(lp.subgates().len(), 1);
+        assert_eq!(lp.pin_equivalences(), vec![vec!["1", "2"], vec!["4", "5"]]);
+        Ok(())
     }
 
     #[test]
-    fn error_path_insufficient_points() {
-        let data = "(pcb test (structure (boundary (path Top 1.0 0 0))))";
+    fn error_logical_part_subgate_without_pin() {
+        let data = "(pcb test (part_library (logical_part p (pin (subgate S1 1 a 0)))))";
         assert!(parse_dsn(data).is_err());
     }
 
     #[test]
-    fn error_on_unexpected_token() {
-        let data = "(pcb test (unknown_keyword))";
-        assert!(parse_dsn(data).is_err());
+    fn pcb_with_electrical_resolutions() -> Result<()> {
+        let data = r"
+            (pcb test
+                (capacitance_resolution farad 1000000)
+                (conductance_resolution mho 1000)
+                (current_resolution amp 1000)
+                (inductance_resolution mhenry 1000)
+                (resistance_resolution kohm 100)
+                (time_resolution sec 1000000000)
+                (voltage_resolution volt 1000)
+            )
+        ";
+        let pcb = parse_dsn(data)?;
+        assert_eq!(
+            pcb.capacitance_resolution,
+            Some(DsnElectricalResolution { unit: DsnElectricalUnit::Farad, amount: 1000000 })
+        );
+        assert_eq!(pcb.conductance_resolution.map(|r| r.unit), Some(DsnElectricalUnit::Mho));
+        assert_eq!(pcb.current_resolution.map(|r| r.amount), Some(1000));
+        assert_eq!(pcb.inductance_resolution.map(|r| r.unit), Some(DsnElectricalUnit::Mhenry));
+        assert_eq!(pcb.resistance_resolution.map(|r| r.amount), Some(100));
+        assert_eq!(pcb.time_resolution.map(|r| r.amount), Some(1000000000));
+        assert_eq!(pcb.voltage_resolution.map(|r| r.unit), Some(DsnElectricalUnit::Volt));
+        Ok(())
     }
 
     #[test]
-    fn error_on_missing_rparen() {
-        let data = "(pcb test";
+    fn error_electrical_resolution_wrong_unit() {
+        let data = "(pcb test (time_resolution volt 1000))";
         assert!(parse_dsn(data).is_err());
     }
 
     #[test]
-    fn error_on_unexpected_eof() {
-        let data = "(pcb test (library";
-        assert!(parse_dsn(data).is_err());
+    fn pcb_with_colors() -> Result<()> {
+        let data = r"
+            (pcb test (colors
+                (color 1 red 255 0 0)
+                (color 2 0 0 255)
+                (set_color Top 1)
+                (set_pattern Top solid)
+            ))
+        ";
+        let pcb = parse_dsn(data)?;
+        let colors = pcb.colors.ok_or_else(|| eyre!("missing colors"))?;
+        assert_eq!(
+            colors.colors[0],
+            DsnColor { color_id: 1, name: "red".into(), rgb: (255, 0, 0) }
+        );
+        assert_eq!(
+            colors.colors[1],
+            DsnColor { color_id: 2, name: String::new(), rgb: (0, 0, 255) }
+        );
+        assert_eq!(colors.set_colors, vec![DsnSetColor { object_id: "Top".into(), color_id: 1 }]);
+        assert_eq!(colors.unknown, vec!["(set_pattern Top solid)"]);
+        Ok(())
     }
 
     #[test]
-    fn side_variants() -> Result<()> {
-        let data_front = "(pcb test (placement (component img1 (place R1 0 0 front 0))))";
-        let data_back = "(pcb test (placement (component img1 (place R1 0 0 back 0))))";
-        let data_both = "(pcb test (placement (component img1 (place R1 0 0 both 0))))";
-
-        let pcb_front = parse_dsn(data_front)?;
-        let pcb_back = parse_dsn(data_back)?;
-        let pcb_both = parse_dsn(data_both)?;
-
-        assert_eq!(pcb_front.placement.components[0].refs[0].side, DsnSide::Front);
-        assert_eq!(pcb_back.placement.components[0].refs[0].side, DsnSide::Back);
-        assert_eq!(pcb_both.placement.components[0].refs[0].side, DsnSide::Both);
-        Ok(())
+    fn error_color_component_out_of_range() {
+        let data = "(pcb test (colors (color 1 256 0 0)))";
+        assert!(parse_dsn(data).is_err());
     }
 
     #[test]
-    fn lock_type_gate() -> Result<()> {
+    fn pcb_with_file_descriptors() -> Result<()> {
         let data =
-            "(pcb test (placement (component img1 (place R1 0 0 front 0 (lock_type gate)))))";
+            "(pcb test (file shared/lib.dsn) (structure (layer Top (type signal))) (file net.dsn))";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.placement.components[0].refs[0].lock_type, DsnLockType::Gate);
+        assert_eq!(pcb.files, vec!["shared/lib.dsn", "net.dsn"]);
+        assert_eq!(pcb.structure.layers.len(), 1);
         Ok(())
     }
 
     #[test]
-    fn all_dimension_units() -> Result<()> {
-        let data_inch = "(pcb test (resolution inch 1000))";
-        let data_mil = "(pcb test (resolution mil 1000))";
-        let data_cm = "(pcb test (resolution cm 1000))";
-        let data_mm = "(pcb test (resolution mm 1000))";
-        let data_um = "(pcb test (resolution um 1000))";
+    fn parse_section_fills_section() -> Result<()> {
+        let pcb = parse_dsn("(pcb test (network (net GND)))")?;
+        let tokens = Lexer::new("(library (image new) (padstack pad1))")?.lex()?;
+        let pcb = Parser::new(&tokens).parse_section(pcb)?;
+        assert_eq!(pcb.pcb_id, "test");
+        assert_eq!(pcb.network.nets[0].net_id, "GND");
+        assert_eq!(pcb.library.images[0].image_id, "new");
+        assert_eq!(pcb.library.padstacks.len(), 1);
+        Ok(())
+    }
 
-        assert_eq!(parse_dsn(data_inch)?.resolution.dimension, DsnDimensionUnit::Inch);
-        assert_eq!(parse_dsn(data_mil)?.resolution.dimension, DsnDimensionUnit::Mil);
-        assert_eq!(parse_dsn(data_cm)?.resolution.dimension, DsnDimensionUnit::Cm);
-        assert_eq!(parse_dsn(data_mm)?.resolution.dimension, DsnDimensionUnit::Mm);
-        assert_eq!(parse_dsn(data_um)?.resolution.dimension, DsnDimensionUnit::Um);
+    #[test]
+    fn error_parse_section_already_present() -> Result<()> {
+        let pcb = parse_dsn("(pcb test (library (image old)))")?;
+        let tokens = Lexer::new("(library (image new))")?.lex()?;
+        assert!(Parser::new(&tokens).parse_section(pcb).is_err());
         Ok(())
     }
 
     #[test]
-    fn parser_directive_ignored() -> Result<()> {
-        let data = "(pcb test (parser (host_cad freeroute)))";
-        let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.pcb_id, "test");
+    fn error_parse_section_wiring() -> Result<()> {
+        let tokens = Lexer::new("(wiring)")?.lex()?;
+        assert!(Parser::new(&tokens).parse_section(DsnPcb::default()).is_err());
         Ok(())
     }
 
     #[test]
-    fn layer_with_property_ignored() -> Result<()> {
-        let data = "(pcb test (structure (layer Top (type signal) (property user_value 123))))";
+    fn keepout_types() -> Result<()> {
+        let data = r"
+            (pcb test (structure
+                (place_keepout (rect Top 0 0 10 10))
+                (bend_keepout (rect Top 0 0 10 10))
+                (elongate_keepout (rect Top 0 0 10 10))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.structure.layers[0].layer_name, "Top");
+        let types: Vec<_> = pcb.structure.keepouts.iter().map(|k| k.keepout_type.clone()).collect();
+        assert_eq!(
+            types,
+            vec![
+                DsnKeepoutType::PlaceKeepout,
+                DsnKeepoutType::BendKeepout,
+                DsnKeepoutType::ElongateKeepout
+            ]
+        );
         Ok(())
     }
 
     #[test]
-    fn clearance_with_default_smd() -> Result<()> {
-        let data = "(pcb test (network (class signal (rule (clearance 0.3 (type default_smd))))))";
+    fn keepout_full() -> Result<()> {
+        let data = r"
+            (pcb test (library (image img1
+                (place_keepout mech1 (sequence_number 2) (rect Top 0 0 10 10)
+                    (rule (clearance 0.5)) (place_rule (spacing 1.5))
+                    (window (rect Top 1 1 2 2)))
+            )))
+        ";
         let pcb = parse_dsn(data)?;
-        match &pcb.network.classes[0].rules[0] {
-            DsnRule::Clearance(c) => {
-                assert_eq!(c.types.len(), 1);
-                match c.types[0] {
-                    DsnClearanceType::DefaultSmd => (),
-                    _ => panic!("Expected DefaultSmd clearance type"),
-                }
-            }
-            DsnRule::Width(_) => panic!("Expected clearance rule"),
-        }
+        let k = &pcb.library.images[0].keepouts[0];
+        assert_eq!(k.keepout_type, DsnKeepoutType::PlaceKeepout);
+        assert_eq!(k.keepout_id, "mech1");
+        assert_eq!(k.sequence_number, Some(2));
+        assert!(matches!(k.shape, DsnShape::Rect(_)));
+        assert!(matches!(k.rules[0], DsnRule::Clearance(_)));
+        assert_eq!(k.place_rules.len(), 1);
+        assert_eq!(k.windows.len(), 1);
         Ok(())
     }
 
     #[test]
-    fn clearance_with_multiple_types() -> Result<()> {
-        let data = "(pcb test (network (class signal (rule (clearance 0.3 (type default_smd) (type smd_smd))))))";
+    fn polygon_and_path_aperture_type() -> Result<()> {
+        let data = r"
+            (pcb test (structure
+                (boundary (polygon Top 1.0 0 0 10 0 10 10 (aperture_type square)))
+                (boundary (path Top 0.5 0 0 10 10 (aperture_type round)))
+                (boundary (path Top 0.5 0 0 10 10))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        match &pcb.network.classes[0].rules[0] {
-            DsnRule::Clearance(c) => {
-                assert_eq!(c.types.len(), 2);
+        let b = &pcb.structure.boundaries;
+        match &b[0] {
+            DsnShape::Polygon(p) => {
+                assert_eq!(p.pts.len(), 3);
+                assert_eq!(p.aperture_type, DsnApertureType::Square);
+            }
+            _ => panic!("Expected polygon shape"),
+        }
+        match (&b[1], &b[2]) {
+            (DsnShape::Path(p1), DsnShape::Path(p2)) => {
+                assert_eq!(p1.aperture_type, DsnApertureType::Round);
+                assert_eq!(p2.aperture_type, DsnApertureType::Round);
             }
-            DsnRule::Width(_) => panic!("Expected clearance rule"),
+            _ => panic!("Expected path shapes"),
         }
         Ok(())
     }
 
     #[test]
-    fn error_invalid_dimension_unit() {
-        let data = "(pcb test (resolution invalid 1000))";
+    fn error_invalid_aperture_type() {
+        let data =
+            "(pcb test (structure (boundary (path Top 0.5 0 0 10 10 (aperture_type oval)))))";
         assert!(parse_dsn(data).is_err());
     }
 
     #[test]
-    fn error_invalid_layer_type() {
-        let data = "(pcb test (structure (layer Top (type invalid))))";
-        assert!(parse_dsn(data).is_err());
+    fn wiring_with_test_points() -> Result<()> {
+        let data = r"
+            (pcb test (wiring (test_points
+                (point 10 20 front Top (net GND))
+                (point 30 40 back)
+            )))
+        ";
+        let pcb = parse_dsn(data)?;
+        let tps = &pcb.wiring.test_points;
+        assert_eq!(
+            tps[0],
+            DsnTestPoint {
+                p: pt(10.0, 20.0),
+                side: Some(DsnSide::Front),
+                layer_id: "Top".into(),
+                net_id: "GND".into()
+            }
+        );
+        assert_eq!(
+            tps[1],
+            DsnTestPoint { p: pt(30.0, 40.0), side: Some(DsnSide::Back), ..Default::default() }
+        );
+        Ok(())
     }
 
     #[test]
-    fn error_invalid_side() {
-        let data = "(pcb test (placement (component img1 (place R1 0 0 invalid 0))))";
-        assert!(parse_dsn(data).is_err());
+    fn wiring_with_test_points_without_side() -> Result<()> {
+        let data = r"
+            (pcb test (wiring (test_points
+                (point 10 20)
+                (point 10 20 Top (net X))
+            )))
+        ";
+        let pcb = parse_dsn(data)?;
+        let tps = &pcb.wiring.test_points;
+        assert_eq!(tps[0], DsnTestPoint { p: pt(10.0, 20.0), ..Default::default() });
+        assert_eq!(
+            tps[1],
+            DsnTestPoint {
+                p: pt(10.0, 20.0),
+                side: None,
+                layer_id: "Top".into(),
+                net_id: "X".into()
+            }
+        );
+        Ok(())
     }
 
     #[test]
-    fn error_invalid_number() {
-        let data = "(pcb test (resolution mm notanumber))";
-        assert!(parse_dsn(data).is_err());
+    fn wiring_with_supply_pins() -> Result<()> {
+        let data = "(pcb test (wiring (supply_pin U1-7 U2-7 (net VCC)) (supply_pin U3-1)))";
+        let pcb = parse_dsn(data)?;
+        let sp = &pcb.wiring.supply_pins;
+        assert_eq!(sp.len(), 2);
+        assert_eq!(sp[0].pins.len(), 2);
+        assert_eq!(sp[0].pins[1].component_id, "U2");
+        assert_eq!(sp[0].net_id, "VCC");
+        assert_eq!(sp[1].pins[0].pin_id, "1");
+        assert!(sp[1].net_id.is_empty());
+        Ok(())
     }
 
     #[test]
-    fn error_missing_required_field() {
-        let data = "(pcb test (resolution))";
+    fn error_test_point_two_layers() {
+        let data = "(pcb test (wiring (test_points (point 0 0 front Top Bottom))))";
         assert!(parse_dsn(data).is_err());
     }
 
     #[test]
-    fn pin_reference_parsing() -> Result<()> {
-        let data = "(pcb test (network (net GND (pins R1-1 C2-2 U3-10 R-A-1 U-1-2))))";
-        let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.network.nets[0].pins.len(), 5);
-        assert_eq!(pcb.network.nets[0].pins[2].component_id, "U3");
-        assert_eq!(pcb.network.nets[0].pins[2].pin_id, "10");
-        assert_eq!(pcb.network.nets[0].pins[3].component_id, "R-A");
-        assert_eq!(pcb.network.nets[0].pins[3].pin_id, "1");
-        assert_eq!(pcb.network.nets[0].pins[4].component_id, "U-1");
-        assert_eq!(pcb.network.nets[0].pins[4].pin_id, "2");
+    fn number_grammar() -> Result<()> {
+        let cases = [
+            ("42", DsnNumber::Integer(42)),
+            ("+7", DsnNumber::Integer(7)),
+            ("-3", DsnNumber::Integer(-3)),
+            ("1.250", DsnNumber::Real("1.250".parse()?)),
+            ("-0.05", DsnNumber::Real("-0.05".parse()?)),
+            (".5", DsnNumber::Real(".5".parse()?)),
+            ("2.", DsnNumber::Real("2.".parse()?)),
+            ("1e5", DsnNumber::Real("1e5".parse()?)),
+            ("-2.5E-3", DsnNumber::Real("-2.5E-3".parse()?)),
+            ("12345678901234567890123", DsnNumber::Real("12345678901234567890123".parse()?)),
+            ("1/3", DsnNumber::Fraction { num: 1, den: 3 }),
+            ("-22/7", DsnNumber::Fraction { num: -22, den: 7 }),
+        ];
+        for (s, n) in cases {
+            assert_eq!(DsnNumber::from_str(s)?, n, "{s}");
+        }
+        assert_eq!(DsnNumber::from_str("1.250")?.to_string(), "1.250");
+        assert_eq!(DsnNumber::from_str("-0.05")?.to_string(), "-0.05");
+        assert_eq!(DsnNumber::from_str("2.")?.to_string(), "2.");
+        assert_eq!(DsnNumber::from_str("+7")?.to_string(), "7");
+        assert_eq!(DsnNumber::from_str("-22/7")?.to_string(), "-22/7");
+        assert_eq!(DsnNumber::from_str("0.1")?.to_f64(), 0.1);
+        assert_eq!(DsnNumber::from_str("1/4")?.to_f64(), 0.25);
+        assert_eq!(DsnNumber::from_str("1e5")?.to_f64(), 100000.0);
+        assert_eq!(DsnNumber::parse_f64("-2.5E-3")?, -0.0025);
+        assert_eq!(DsnNumber::parse_f64("-1/8")?, -0.125);
+        for s in [
+            "", "-", ".", "e5", "1e", "1e+", "inf", "nan", "1/0", "1/-2", "1.2.3", "--1", "0x10",
+            "1/2.5",
+        ] {
+            assert!(DsnNumber::from_str(s).is_err(), "{s}");
+            assert!(DsnNumber::parse_f64(s).is_err(), "{s}");
+        }
+        assert!(DsnReal::from_str("abc").is_err());
+        assert!(DsnReal::from_str("1/2").is_err());
         Ok(())
     }
 
     #[test]
-    fn error_invalid_pin_reference() {
-        let data = "(pcb test (network (net GND (pins R1_invalid))))";
-        assert!(parse_dsn(data).is_err());
-    }
-
-    #[test]
-    fn multiple_images_in_library() -> Result<()> {
-        let data = "(pcb test (library (image img1) (image img2) (image img3)))";
+    fn exact_numbers() -> Result<()> {
+        let data = r"
+            (pcb test (structure
+                (boundary (path Top 0 0 0 1.10 2))
+                (rule (width 1/3) (clearance 0.1000))
+            ))
+        ";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.library.images.len(), 3);
+        let DsnRule::Width(width) = pcb.structure.rules[0] else {
+            return Err(eyre!("expected width rule"));
+        };
+        let exact = pcb.exact_number(width).map(ToString::to_string);
+        assert_eq!(exact.as_deref(), Some("1/3"));
+        let exact = pcb.exact_number(0.1).map(ToString::to_string);
+        assert_eq!(exact.as_deref(), Some("0.1000"));
+        let exact = pcb.exact_number(1.1).map(ToString::to_string);
+        assert_eq!(exact.as_deref(), Some("1.10"));
+        assert_eq!(pcb.exact_number(0.25), None);
         Ok(())
     }
 
     #[test]
-    fn multiple_nets_in_network() -> Result<()> {
-        let data = "(pcb test (network (net GND) (net VCC) (net DATA)))";
+    fn fraction_and_signed_numbers() -> Result<()> {
+        let data = "(pcb test (structure (boundary (path Top 1/2 +1 -1/4 3.5 .5))))";
         let pcb = parse_dsn(data)?;
-        assert_eq!(pcb.network.nets.len(), 3);
+        match &pcb.structure.boundaries[0] {
+            DsnShape::Path(p) => {
+                assert_eq!(p.aperture_width, 0.5);
+                assert_eq!(p.pts, vec![pt(1.0, -0.25), pt(3.5, 0.5)]);
+            }
+            _ => panic!("Expected path shape"),
+        }
         Ok(())
     }
 
     #[test]
-    fn empty_sections() -> Result<()> {
-        let data = "(pcb test (library) (network) (placement) (wiring))";
-        let pcb = parse_dsn(data)?;
-        assert!(pcb.library.images.is_empty());
-        assert!(pcb.library.padstacks.is_empty());
-        assert!(pcb.network.nets.is_empty());
-        assert!(pcb.network.classes.is_empty());
-        assert!(pcb.placement.components.is_empty());
-        assert!(pcb.wiring.wires.is_empty());
-        assert!(pcb.wiring.vias.is_empty());
+    fn integer_rejects_non_integers() -> Result<()> {
+        assert_eq!(parse_dsn("(pcb test (resolution mm +1000))")?.resolution.amount, 1000);
+        assert!(parse_dsn("(pcb test (resolution mm 1000.5))").is_err());
+        assert!(parse_dsn("(pcb test (resolution mm 1/2))").is_err());
         Ok(())
     }
 }
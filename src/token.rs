@@ -5,73 +5,237 @@ use strum::{Display as EnumDisplay, EnumString};
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, EnumString, EnumDisplay)]
 #[strum(serialize_all = "snake_case")]
 pub enum Tok {
+    Absolute,
+    ApertureType,
     Area,
+    Array,
     Attach,
+    Attr,
     Back,
+    BendKeepout,
     Both,
     Boundary,
+    Bundle,
+    CapacitanceResolution,
+    CenterCenter,
+    CheckingTrimByPin,
     Circle,
     Circuit,
     Class,
+    ClassClass,
+    Classes,
     Clearance,
+    Cluster,
     Cm,
+    Color,
+    Colors,
+    Comp,
+    CompEdgeCenter,
     Component,
+    Composite,
+    ConductanceResolution,
+    Conductor,
     Connect,
-    DefaultSmd,
+    Contact,
+    Control,
+    Cost,
+    CurrentResolution,
+    Delay,
+    Direction,
+    Directory,
+    EffectiveViaLength,
+    ElongateKeepout,
+    Exclude,
+    Expose,
+    ExtraImageDirectory,
+    Family,
+    FamilyFamily,
+    File,
+    Fit,
+    Fix,
+    FlipStyle,
+    FloorPlan,
+    ForceToTerminalPoint,
+    Fromto,
     Front,
+    Gap,
     Gate,
+    Grid,
+    Group,
+    GroupSet,
+    Height,
     Image,
+    ImageImage,
+    ImageType,
     Inch,
+    Include,
+    InductanceResolution,
+    InterLayerClearance,
     Jumper,
+    JunctionType,
     Keepout,
     Layer,
+    LayerDepth,
+    LayerNoiseWeight,
+    LayerPair,
+    LayerRule,
+    Length,
+    LengthAmplitude,
+    LengthFactor,
+    LengthGap,
     Library,
+    Limit,
+    LimitBends,
+    LimitCrossing,
+    LimitVias,
+    LimitWay,
     Literal,
+    Load,
     LockType,
+    LogicalPart,
+    LogicalPartMapping,
     #[strum(serialize = "(")]
     Lparen,
+    MatchFromtoDelay,
+    MatchFromtoLength,
+    MatchGroupDelay,
+    MatchGroupLength,
+    MatchNetDelay,
+    MatchNetLength,
+    MaxNoise,
+    MaxRestrictedLayerLength,
+    MaxStagger,
+    MaxStub,
+    MaxTotalVias,
     Mil,
+    Mirror,
     Mixed,
     Mm,
     Net,
+    NetNumber,
+    Nets,
     Network,
+    Noexpose,
+    Normal,
     Off,
+    OffGrid,
+    Offset,
     On,
+    OppositeSide,
+    Order,
     Outline,
     Padstack,
+    Pair,
+    ParallelNoise,
+    ParallelSegment,
     Parser,
+    PartLibrary,
     Path,
     Pcb,
+    PermitOrient,
+    PermitSide,
+    PhysicalPartMapping,
     Pin,
+    PinAllow,
     Pins,
+    PinWidthTaper,
     Place,
+    PlaceBoundary,
+    PlaceControl,
+    PlaceKeepout,
     Placement,
+    PlaceRule,
     Plane,
     Pn,
+    Point,
     Polygon,
     Position,
     Power,
+    PowerFanout,
+    Prefix,
+    Priority,
     Property,
+    Protect,
     Qarc,
     Rect,
     Reduced,
+    RedundantWiring,
+    Region,
+    RelativeDelay,
+    RelativeGroupDelay,
+    RelativeGroupLength,
+    RelativeLength,
+    Reorder,
+    ResistanceResolution,
     Resolution,
+    RestrictedLayerLengthFactor,
+    Room,
     Rotate,
+    Route,
+    RouteToFanoutOnly,
     #[strum(serialize = ")")]
     Rparen,
     Rule,
+    SameNetChecking,
+    SampleWindow,
+    SaturationLength,
+    SequenceNumber,
+    SetColor,
     Shape,
+    Shield,
+    ShieldGap,
+    ShieldLoop,
+    ShieldTieDownInterval,
+    ShieldWidth,
+    Side,
     Signal,
     Smd,
-    SmdSmd,
+    Source,
+    Spacing,
+    StackVia,
+    StackViaDepth,
+    Status,
     Structure,
+    Subgate,
+    Suffix,
+    Supply,
+    SupplyPin,
+    SwitchWindow,
+    TandemNoise,
+    TandemSegment,
+    TandemShieldOverhang,
+    Terminal,
+    Terminator,
     Testpoint,
+    TestpointRule,
+    TestPoints,
+    Threshold,
+    TimeLengthFactor,
+    TimeResolution,
+    Tjunction,
+    Topology,
+    TotalDelay,
+    TotalLength,
+    TrackId,
+    Turret,
     Type,
     Um,
+    Unassigned,
     Unit,
+    UseArray,
+    UseLayer,
+    UseNet,
     UseVia,
+    ViaArrayTemplate,
+    ViaAtSmd,
     ViaKeepout,
     Via,
+    ViaNumber,
+    ViaPattern,
+    ViaSite,
+    VirtualPin,
+    VoltageResolution,
+    Weight,
     Width,
     Window,
     WireKeepout,
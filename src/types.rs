@@ -1,12 +1,157 @@
-use memegeom::primitive::Rt;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use eyre::{Result, eyre};
 use memegeom::primitive::point::Pt;
+use memegeom::primitive::{Rt, pt};
 use strum::{Display as EnumDisplay, EnumString};
 
 // Types defined in DSN specification.
 
 // <number> = [<sign>] (<positive_integer> | <real> | <fraction>)
+// <sign> = [+ | -]
+// <real> = [<positive_integer> . | <positive_integer> . <positive_integer> |
+//    . <positive_integer>]
+// <fraction> = <positive_integer> / <positive_integer>
 // <dimension> = <number>
 // <vertex> = PtF
+// Keeps the exact value of a number, so it can be written back out without
+// floating point error. Reals are kept as written, but integers and fractions
+// are stored as values, so e.g. "+7" is written back out as "7". Equality
+// compares numbers as written, so e.g. 1/2 and 0.5 are not equal.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DsnNumber {
+    Integer(i64),
+    Real(DsnReal),
+    Fraction { num: i64, den: i64 }, // |den| is always positive.
+}
+
+impl DsnNumber {
+    // Parses a number straight to f64, without keeping its exact form.
+    pub fn parse_f64(s: &str) -> Result<f64> {
+        if let Some((num, den)) = fraction(s)? {
+            return Ok(num as f64 / den as f64);
+        }
+        check_decimal(s)?;
+        Ok(f64::from_str(s)?)
+    }
+
+    #[must_use]
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Self::Integer(v) => *v as f64,
+            Self::Real(v) => v.to_f64(),
+            Self::Fraction { num, den } => *num as f64 / *den as f64,
+        }
+    }
+
+    // Returns the value if it was written as an integer that fits in an i32.
+    #[must_use]
+    pub fn to_i32(&self) -> Option<i32> {
+        match *self {
+            Self::Integer(v) => i32::try_from(v).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for DsnNumber {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some((num, den)) = fraction(s)? {
+            return Ok(Self::Fraction { num, den });
+        }
+        // Integers too large for i64 are kept as written, like reals.
+        match i64::from_str(s) {
+            Ok(v) => Ok(Self::Integer(v)),
+            Err(_) => Ok(Self::Real(DsnReal::from_str(s)?)),
+        }
+    }
+}
+
+impl fmt::Display for DsnNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Integer(v) => write!(f, "{v}"),
+            Self::Real(v) => write!(f, "{v}"),
+            Self::Fraction { num, den } => write!(f, "{num}/{den}"),
+        }
+    }
+}
+
+// A real kept as written, e.g. "1.250", "2." or "1e5". Can only be made from
+// valid text, so it always converts to f64.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DsnReal(String);
+
+impl DsnReal {
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    #[must_use]
+    pub fn to_f64(&self) -> f64 {
+        // The text was checked when this was made, so this can't fail.
+        f64::from_str(&self.0).unwrap_or(f64::NAN)
+    }
+}
+
+impl FromStr for DsnReal {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        check_decimal(s)?;
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl fmt::Display for DsnReal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Returns the parts of |s| if it is a fraction, or None if it has no '/'.
+fn fraction(s: &str) -> Result<Option<(i64, i64)>> {
+    let Some((num, den)) = s.split_once('/') else {
+        return Ok(None);
+    };
+    let is_digits = |d: &str| !d.is_empty() && d.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits(num.strip_prefix(['-', '+']).unwrap_or(num)) || !is_digits(den) {
+        return Err(eyre!("invalid fraction {}", s));
+    }
+    let num = i64::from_str(num).map_err(|_| eyre!("number {} out of range", s))?;
+    let den = i64::from_str(den).map_err(|_| eyre!("number {} out of range", s))?;
+    if den == 0 {
+        return Err(eyre!("fraction {} has zero denominator", s));
+    }
+    Ok(Some((num, den)))
+}
+
+// Checks |s| is a valid integer or real. Besides the spec's grammar, reals may
+// have an exponent, e.g. "1e5".
+fn check_decimal(s: &str) -> Result<()> {
+    let body = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (mantissa, exp) = match body.split_once(['e', 'E']) {
+        Some((m, e)) => (m, Some(e.strip_prefix(['-', '+']).unwrap_or(e))),
+        None => (body, None),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = |d: &str| d.bytes().all(|b| b.is_ascii_digit());
+    if (int.is_empty() && frac.is_empty())
+        || !digits(int)
+        || !digits(frac)
+        || exp.is_some_and(|e| e.is_empty() || !digits(e))
+    {
+        return Err(eyre!("invalid number {}", s));
+    }
+    Ok(())
+}
 
 // <unit_descriptor> = (unit <dimension_unit>)
 // <dimension_unit> = [inch | mil | cm | mm | um]
@@ -30,6 +175,16 @@ pub type DsnId = String;
 // <layer_id> = <id> | pcb | signal | power
 pub type DsnLayerId = DsnId;
 
+// <user_property_descriptor> = (property {<property_value_descriptor>})
+// <property_value_descriptor> = (<property_key> <property_value>)
+// Some tools also write a single property as (property <key> <value>).
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnProperty {
+    pub key: DsnId,
+    pub value: DsnId,
+}
+
 // <rectangle_descriptor> = (rect <layer_id> <vertex> <vertex>)
 #[must_use]
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -47,6 +202,17 @@ pub struct DsnCircle {
     pub p: Pt, // Defaults to PCB origin.
 }
 
+// Shape of the aperture used to draw a polygon or path. Round apertures give
+// rounded corners and ends, square apertures give square ones.
+#[must_use]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnApertureType {
+    #[default]
+    Round,
+    Square,
+}
+
 // <polygon_descriptor> = (polygon <layer_id> <aperture_width> {<vertex>}
 //    [(aperture_type [round | square])])
 #[must_use]
@@ -55,6 +221,7 @@ pub struct DsnPolygon {
     pub layer_id: DsnLayerId,
     pub aperture_width: f64,
     pub pts: Vec<Pt>,
+    pub aperture_type: DsnApertureType,
 }
 
 // <path_descriptor> = (path <layer_id> <aperture_width> {<vertex>}
@@ -65,6 +232,7 @@ pub struct DsnPath {
     pub layer_id: DsnLayerId,
     pub aperture_width: f64,
     pub pts: Vec<Pt>,
+    pub aperture_type: DsnApertureType,
 }
 
 // <qarc_descriptor> = (qarc <layer_id> <aperture_width>
@@ -129,9 +297,18 @@ pub enum DsnObjectType {
 }
 
 #[must_use]
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct DsnPadstackShape {
     pub shape: DsnShape,
+    pub reduced: Option<DsnShape>, // Smaller shape used where clearance is tight.
+    pub connect: bool,             // Whether wires may connect to this shape. Default is on.
+    pub windows: Vec<DsnWindow>,
+}
+
+impl Default for DsnPadstackShape {
+    fn default() -> Self {
+        Self { shape: DsnShape::default(), reduced: None, connect: true, windows: Vec::new() }
+    }
 }
 
 // <attach_descriptor> = (attach [off | on [(use_via <via_id>)]])
@@ -141,19 +318,36 @@ pub struct DsnPadstackShape {
 //        [(connect [on | off])] [{<window_descriptor>}])}
 //    [<attach_descriptor>] [{<pad_via_site_descriptor>}] [(rotate [on | off])]
 //    [(absolute [on | off])] [(rule <clearance_descriptor>)])
+// <pad_via_site_descriptor> = (via_site <vertex>)
 // A padstack describes an exposed area for connecting components to. Pins
 // connect onto padstacks. There are multiple PadstackShapes
 #[must_use]
 #[derive(Debug, Clone, PartialEq)]
 pub struct DsnPadstack {
     pub padstack_id: DsnId,
+    pub unit: Option<DsnDimensionUnit>, // Overrides the enclosing unit if present.
     pub shapes: Vec<DsnPadstackShape>,
-    pub attach: bool, // Default is to allow vias under SMD pads.
+    pub attach: bool,   // Default is to allow vias under SMD pads.
+    pub use_via: DsnId, // Via to use when attaching. Empty if unspecified.
+    pub pad_via_sites: Vec<Pt>,
+    pub rotate: bool, // Whether the padstack rotates with its component. Default is on.
+    pub absolute: bool, // Whether shapes ignore the component's mirroring. Default is off.
+    pub rules: Vec<DsnRule>,
 }
 
 impl Default for DsnPadstack {
     fn default() -> Self {
-        Self { padstack_id: String::new(), shapes: Vec::new(), attach: true }
+        Self {
+            padstack_id: String::new(),
+            unit: None,
+            shapes: Vec::new(),
+            attach: true,
+            use_via: String::new(),
+            pad_via_sites: Vec::new(),
+            rotate: true,
+            absolute: false,
+            rules: Vec::new(),
+        }
     }
 }
 
@@ -183,9 +377,59 @@ pub struct DsnPin {
     pub p: Pt,              // Location of the pin relative to the parent component (placement).
 }
 
+// <pin_array_descriptor> = (array <begin_index> <number_of_pins>
+//    <x_pitch> <y_pitch> <vertex> [<pin_prefix>])
+// Describes a row of pins sharing a padstack. Pin i (from zero) is at
+// |p| + i * (|x_pitch|, |y_pitch|) and is named |prefix| followed by
+// |begin_index| + i.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnPinArray {
+    pub padstack_id: DsnId,
+    pub rotation: f64,
+    pub begin_index: i32,
+    pub count: i32,
+    pub x_pitch: f64,
+    pub y_pitch: f64,
+    pub p: Pt, // Location of the first pin.
+    pub prefix: DsnId,
+}
+
+// Upper bound on how many items a pin array or composite name list may
+// expand to, so a malformed file cannot force a huge allocation.
+pub const MAX_EXPANSION: i32 = 1_000_000;
+
+impl DsnPinArray {
+    // Checks that the pin count is in range and the pin indices fit in an i32.
+    pub fn validate(&self) -> Result<()> {
+        if !(0..=MAX_EXPANSION).contains(&self.count) {
+            return Err(eyre!("pin array count {} out of range", self.count));
+        }
+        if self.count > 0 && self.begin_index.checked_add(self.count - 1).is_none() {
+            return Err(eyre!("pin array indices overflow from {}", self.begin_index));
+        }
+        Ok(())
+    }
+
+    pub fn pins(&self) -> Result<Vec<DsnPin>> {
+        self.validate()?;
+        Ok((0..self.count)
+            .map(|i| DsnPin {
+                padstack_id: self.padstack_id.clone(),
+                rotation: self.rotation,
+                pin_id: format!("{}{}", self.prefix, self.begin_index + i),
+                p: pt(self.p.x + i as f64 * self.x_pitch, self.p.y + i as f64 * self.y_pitch),
+            })
+            .collect())
+    }
+}
+
 // Keepout: No routing whatsoever.
+// PlaceKeepout: No component placement.
 // ViaKeepout: No vias.
 // WireKeepout: No wires.
+// BendKeepout: No wire bends.
+// ElongateKeepout: No wire elongation (accordion patterns for length matching).
 #[must_use]
 #[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
 #[strum(serialize_all = "snake_case")]
@@ -193,8 +437,11 @@ pub struct DsnPin {
 pub enum DsnKeepoutType {
     #[default]
     Keepout,
+    PlaceKeepout,
     ViaKeepout,
     WireKeepout,
+    BendKeepout,
+    ElongateKeepout,
 }
 
 // <keepout_descriptor> = (
@@ -204,12 +451,18 @@ pub enum DsnKeepoutType {
 //    [(rule <clearance_descriptor>)]
 //    [(place_rule <spacing_descriptor>)]
 //    [{<window_descriptor>}])
-// Describes an area where no routing can occur.
+// Describes an area where routing or placement is restricted. What is
+// restricted depends on |keepout_type|.
 #[must_use]
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnKeepout {
     pub keepout_type: DsnKeepoutType,
+    pub keepout_id: DsnId, // Empty if unnamed.
+    pub sequence_number: Option<i32>,
     pub shape: DsnShape,
+    pub rules: Vec<DsnRule>,
+    pub place_rules: Vec<DsnPlaceRule>,
+    pub windows: Vec<DsnWindow>, // Areas within |shape| excluded from the keepout.
 }
 
 // <image_descriptor> = (image <image_id>
@@ -224,25 +477,87 @@ pub struct DsnKeepout {
 //    [<place_rule_descriptor>]
 //    [{<keepout_descriptor>}]
 //    [<image_property_descriptor>])
+// <conductor_shape_descriptor> = (conductor <shape_descriptor>)
+// <conductor_via_descriptor> = <wire_via_descriptor>
 // Describes a component type.
 #[must_use]
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnImage {
     pub image_id: DsnId,
+    pub side: Option<DsnSide>, // Side the image may be placed on. None if unrestricted.
+    pub unit: Option<DsnDimensionUnit>, // Overrides the enclosing unit if present.
     pub outlines: Vec<DsnShape>,
     pub pins: Vec<DsnPin>,
+    pub pin_arrays: Vec<DsnPinArray>,
+    pub conductors: Vec<DsnShape>, // Copper that is part of the footprint itself.
+    pub conductor_vias: Vec<DsnVia>,
+    pub rules: Vec<DsnRule>,
+    pub place_rules: Vec<DsnPlaceRule>,
     pub keepouts: Vec<DsnKeepout>,
+    pub properties: Vec<DsnProperty>,
+}
+
+impl DsnImage {
+    // Returns all pins in the image, with pin arrays expanded into individual pins.
+    pub fn expanded_pins(&self) -> Result<Vec<DsnPin>> {
+        let mut pins = self.pins.clone();
+        for array in &self.pin_arrays {
+            pins.extend(array.pins()?);
+        }
+        Ok(pins)
+    }
 }
 
+// Position: The component may not be moved.
+// Gate: Gates may not be swapped.
+// Subgate: Subgates may not be swapped.
+// Pin: Pins may not be swapped.
 #[must_use]
 #[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
 #[strum(serialize_all = "snake_case")]
-#[derive(Default)]
 pub enum DsnLockType {
-    #[default]
-    None,
     Position,
     Gate,
+    Subgate,
+    Pin,
+}
+
+// <mirror_descriptor> = (mirror [x | y | xy | off])
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+#[derive(Default)]
+pub enum DsnMirror {
+    X,
+    Y,
+    Xy,
+    #[default]
+    Off,
+}
+
+// <component_status_descriptor> = (status [added | deleted | substituted])
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnComponentStatus {
+    Added,
+    Deleted,
+    Substituted,
+}
+
+// <region_descriptor> = (region [<region_id>]
+//    [<rectangle_descriptor> | <polygon_descriptor>]
+//    [(net <net_id>) | (class <class_id>)]
+//    [<rule_descriptor>])
+// Describes an area with its own routing rules.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnRegion {
+    pub region_id: DsnId,
+    pub shape: Option<DsnShape>, // Must be rect or polygon.
+    pub net_id: DsnId,           // Empty if the region is not restricted to a net.
+    pub class_id: DsnId,         // Empty if the region is not restricted to a class.
+    pub rules: Vec<DsnRule>,
 }
 
 // <placement_reference> = (place <component_id>
@@ -260,10 +575,17 @@ pub enum DsnLockType {
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnPlacementRef {
     pub component_id: DsnId,
-    pub p: Pt,
-    pub side: DsnSide,
-    pub rotation: f64,
-    pub lock_type: DsnLockType,
+    pub p: Option<Pt>, // None if the component is unplaced.
+    pub side: DsnSide, // Only meaningful if the component is placed.
+    pub rotation: f64, // Only meaningful if the component is placed.
+    pub mirror: DsnMirror,
+    pub status: Option<DsnComponentStatus>,
+    pub logical_part: DsnId,
+    pub place_rules: Vec<DsnPlaceRule>,
+    pub properties: Vec<DsnProperty>,
+    pub lock_types: Vec<DsnLockType>,
+    pub rules: Vec<DsnRule>,
+    pub region: Option<DsnRegion>,
     pub part_number: DsnId,
 }
 
@@ -307,7 +629,80 @@ pub struct DsnPinRef {
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnNet {
     pub net_id: DsnId,
+    pub unassigned: bool,
+    pub net_number: Option<i32>,
     pub pins: Vec<DsnPinRef>, // Of the form: ComponentId-PinId
+    pub ordered: bool,        // If pins came from (order ...) and must be connected in order.
+    pub net_type: Option<DsnNetType>,
+    pub properties: Vec<DsnProperty>,
+    pub circuits: Vec<DsnCircuit>,
+    pub rules: Vec<DsnRule>,
+    pub layer_rules: Vec<DsnLayerRule>,
+    pub fromtos: Vec<DsnFromTo>,
+    pub expose: Vec<DsnPinRef>,
+    pub noexpose: Vec<DsnPinRef>,
+    pub source: Vec<DsnPinRef>,
+    pub load: Vec<DsnPinRef>,
+    pub terminator: Vec<DsnPinRef>,
+    pub supply: Option<DsnSupplyType>,
+}
+
+// Fix: Wiring on the net may not be changed.
+// Normal: Wiring on the net may be changed.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnNetType {
+    Fix,
+    Normal,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnSupplyType {
+    Power,
+    Ground,
+}
+
+// <layer_rule_descriptor> = (layer_rule {<layer_name>} <rule_descriptor>)
+// Rules that only apply on the given layers.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnLayerRule {
+    pub layer_names: Vec<DsnId>,
+    pub rules: Vec<DsnRule>,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnFromToType {
+    Fix,
+    Normal,
+    Soft,
+}
+
+// <fromto_descriptor> = {(fromto
+//    [<pin_reference> | <virtual_pin_name> | <component_id>]
+//    [<pin_reference> | <virtual_pin_name> | <component_id>]
+//    [(type [fix | normal | soft])]
+//    [(net <net_id>)]
+//    [<rule_descriptor>]
+//    [<circuit_descriptor>]
+//    [{<layer_rule_descriptor>}])}
+// Describes a single connection within a net. The endpoints are kept as
+// written since they may refer to pins, virtual pins or components.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnFromTo {
+    pub from: DsnId,
+    pub to: DsnId,
+    pub fromto_type: Option<DsnFromToType>,
+    pub net_id: DsnId,
+    pub rules: Vec<DsnRule>,
+    pub circuits: Vec<DsnCircuit>,
+    pub layer_rules: Vec<DsnLayerRule>,
 }
 
 // <circuit_descriptors> = [<delay_descriptor> |
@@ -336,7 +731,110 @@ pub struct DsnNet {
 #[must_use]
 #[derive(Debug, Clone, PartialEq)]
 pub enum DsnCircuit {
-    UseVia(DsnId), // Padstack id of via to use
+    Delay(DsnLimit),
+    TotalDelay(DsnLimit),
+    Length(DsnLimit),
+    TotalLength(DsnLimit),
+    MatchFromtoLength(DsnMatch),
+    MatchFromtoDelay(DsnMatch),
+    MatchGroupLength(DsnMatch),
+    MatchGroupDelay(DsnMatch),
+    MatchNetLength(DsnMatch),
+    MatchNetDelay(DsnMatch),
+    RelativeDelay(DsnRelative),
+    RelativeGroupDelay(DsnRelative),
+    RelativeGroupLength(DsnRelative),
+    RelativeLength(DsnRelative),
+    SampleWindow(DsnTimeWindow),
+    SwitchWindow(DsnTimeWindow),
+    Shield(DsnShield),
+    MaxRestrictedLayerLength(f64),
+    Priority(i32),
+    UseLayer(Vec<DsnLayerId>),
+    UseVia(Vec<DsnUseVia>),
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnLimitType {
+    Ratio,
+    Actual,
+}
+
+// <length_descriptor> = (length <max_length> [<min_length>] [(type [ratio | actual])])
+// Also used for delay, total_delay and total_length. A negative maximum means
+// no limit.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnLimit {
+    pub max: f64,
+    pub min: Option<f64>,
+    pub limit_type: Option<DsnLimitType>,
+}
+
+// <match_net_length_descriptor> = (match_net_length [on | off]
+//    [<tolerance>] [(type [ratio | actual])])
+// Shared by all the match_* descriptors.
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DsnMatch {
+    pub on: bool,
+    pub tolerance: Option<f64>,
+    pub match_type: Option<DsnLimitType>,
+}
+
+impl Default for DsnMatch {
+    fn default() -> Self {
+        Self { on: true, tolerance: None, match_type: None }
+    }
+}
+
+// <relative_length_descriptor> = (relative_length <reference_id> <delta> <tolerance>)
+// Shared by all the relative_* descriptors. The reference is a fromto or group
+// depending on the descriptor.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnRelative {
+    pub reference_id: DsnId,
+    pub delta: f64,
+    pub tolerance: f64,
+}
+
+// <sample_window_descriptor> = (sample_window <begin_time> <end_time>)
+// <switch_window_descriptor> = (switch_window <begin_time> <end_time>)
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnTimeWindow {
+    pub begin: f64,
+    pub end: f64,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnShieldType {
+    Parallel,
+    Tandem,
+    Coax,
+}
+
+// <shield_descriptor> = (shield [on | off] [(type [parallel | tandem | coax])]
+//    [(use_net <net_id>)])
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnShield {
+    pub on: bool,
+    pub shield_type: Option<DsnShieldType>,
+    pub net_id: DsnId, // Net used for the shield, if specified.
+}
+
+// (use_via {[<padstack_id> | (use_array <via_array_template_id> [<row> <column>])]})
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DsnUseVia {
+    Padstack(DsnId),
+    Array { template_id: DsnId, rows: Option<i32>, columns: Option<i32> },
 }
 
 // <clearance_type> = [<object_type>_<object_type> | smd_via_same_net |
@@ -349,7 +847,13 @@ pub enum DsnClearanceType {
     // This is not part of the official spec but default seems to be used to
     // mean wildcard for any type (and overriden by specific designations)
     DefaultSmd,
-    SmdSmd,
+    Pair(DsnObjectType, DsnObjectType), // <object_type>_<object_type>, e.g. wire_via.
+    SmdViaSameNet,
+    ViaViaSameNet,
+    BuriedViaGap(Option<i32>), // Layer depth, if specified.
+    AntipadGap,
+    PadToTurnGap,
+    SmdToTurnGap,
 }
 // <clearance_descriptor> = (clearance <positive_dimension> [(type {<clearance_type>})]
 #[must_use]
@@ -359,6 +863,38 @@ pub struct DsnClearance {
     pub types: Vec<DsnClearanceType>,
 }
 
+// <spacing_descriptor> = (spacing <positive_dimension> [(type <spacing_type>)])
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnSpacing {
+    pub amount: f64,
+    pub spacing_type: DsnId, // e.g. smd_smd. Empty if it applies to everything.
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnPermitOrient {
+    Horizontal,
+    Vertical,
+    All,
+    Off,
+}
+
+// <place_rule_descriptor> = (place_rule {<spacing_descriptor> |
+//    (permit_orient [horizontal | vertical | all | off]) |
+//    (permit_side [front | back | both | off]) |
+//    (opposite_side [on | off])})
+// Describes rules about placing components.
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DsnPlaceRule {
+    Spacing(DsnSpacing),
+    PermitOrient(DsnPermitOrient),
+    PermitSide(Option<DsnSide>), // None if off.
+    OppositeSide(bool),
+}
+
 // <rule_descriptor> = (rule {<rule_descriptors>})
 // <rule_descriptors> =
 //    [<clearance_descriptor> |
@@ -400,11 +936,177 @@ pub struct DsnClearance {
 //    <via_at_smd_descriptor> |
 //    <via_pattern_descriptor> |
 //    <width_descriptor>]
+// <effective_via_length_descriptor> = (effective_via_length <positive_dimension>)
+// <interlayer_clearance_descriptor> = (inter_layer_clearance <positive_dimension>
+//    {(layer_pair <layer_id> <layer_id>)})
+// <junction_type_descriptor> = (junction_type [term_only | all])
+// <length_amplitude_descriptor> = (length_amplitude <min_dimension> <max_dimension>)
+// <length_factor_descriptor> = (length_factor <positive_number>)
+// <length_gap_descriptor> = (length_gap <positive_dimension>)
+// <limit_bends_descriptor> = (limit_bends <positive_integer>)
+// <limit_crossing_descriptor> = (limit_crossing <positive_integer>)
+// <limit_vias_descriptor> = (limit_vias <positive_integer>)
+// <limit_way_descriptor> = (limit_way <positive_integer>)
+// <max_noise_descriptor> = (max_noise <real>)
+// <max_stagger_descriptor> = (max_stagger <positive_dimension>)
+// <max_stub_descriptor> = (max_stub <positive_dimension>)
+// <max_total_vias_descriptor> = (max_total_vias [<positive_integer> | -1])
+// <parallel_noise_descriptor>, <tandem_noise_descriptor> = <noise_descriptor>
+// <parallel_segment_descriptor>, <tandem_segment_descriptor> = <segment_descriptor>
+// <pin_width_taper_descriptor> = (pin_width_taper [on | off])
+// <power_fanout_descriptor> = (power_fanout [on | off])
+// <redundant_wiring_descriptor> = (redundant_wiring [on | off])
+// <reorder_descriptor> = (reorder [daisy | stagger])
+// <restricted_layer_length_factor_descriptor> =
+//    (restricted_layer_length_factor <positive_number>)
+// <saturation_length_descriptor> = (saturation_length <positive_dimension>)
+// <shield_gap_descriptor> = (shield_gap <positive_dimension>)
+// <shield_loop_descriptor> = (shield_loop [open | closed])
+// <shield_tie_down_interval_descriptor> = (shield_tie_down_interval <positive_dimension>)
+// <shield_width_descriptor> = (shield_width <positive_dimension>)
+// <stack_via_descriptor> = (stack_via {<padstack_id>})
+// <stack_via_depth_descriptor> = (stack_via_depth <positive_integer>
+//    [(layer_pair <layer_id> <layer_id>)])
+// <tandem_shield_overhang_descriptor> = (tandem_shield_overhang <positive_dimension>)
+// <time_length_factor_descriptor> = (time_length_factor <positive_number>)
+// <tjunction_descriptor> = (tjunction [on | off])
+// <track_id_descriptor> = (track_id <id>)
+// <via_pattern_descriptor> = (via_pattern [on | off])
+// <width_descriptor> = (width <positive_dimension>)
 #[must_use]
 #[derive(Debug, Clone, PartialEq)]
 pub enum DsnRule {
     Width(f64),
     Clearance(DsnClearance),
+    EffectiveViaLength(f64),
+    InterLayerClearance(DsnInterLayerClearance),
+    JunctionType(DsnJunctionType),
+    LengthAmplitude(f64, f64), // Min and max.
+    LengthFactor(f64),
+    LengthGap(f64),
+    LimitBends(i32),
+    LimitCrossing(i32),
+    LimitVias(i32),
+    LimitWay(i32),
+    MaxNoise(f64),
+    MaxStagger(f64),
+    MaxStub(f64),
+    MaxTotalVias(i32), // -1 means unlimited.
+    ParallelNoise(DsnNoise),
+    ParallelSegment(DsnSegment),
+    PinWidthTaper(bool),
+    PowerFanout(bool),
+    RedundantWiring(bool),
+    Reorder(DsnReorder),
+    RestrictedLayerLengthFactor(f64),
+    SaturationLength(f64),
+    ShieldGap(f64),
+    ShieldLoop(DsnShieldLoop),
+    ShieldTieDownInterval(f64),
+    ShieldWidth(f64),
+    StackVia(Vec<DsnId>),
+    StackViaDepth(DsnStackViaDepth),
+    TandemNoise(DsnNoise),
+    TandemSegment(DsnSegment),
+    TandemShieldOverhang(f64),
+    TestpointRule(DsnTestpointRule),
+    TimeLengthFactor(f64),
+    Tjunction(bool),
+    TrackId(DsnId),
+    ViaAtSmd(DsnViaAtSmd),
+    ViaPattern(bool),
+    // Rules this library does not know about, e.g. vendor extensions, kept
+    // as the original s-expression. Quoting is not preserved.
+    Unknown(String),
+}
+
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnInterLayerClearance {
+    pub amount: f64,
+    pub layer_pairs: Vec<(DsnLayerId, DsnLayerId)>, // Empty if it applies to all layers.
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnJunctionType {
+    TermOnly,
+    All,
+}
+
+// <noise_descriptor> = ([parallel_noise | tandem_noise]
+//    [(gap <positive_dimension>)] [(threshold <positive_dimension>)]
+//    [(weight <real>)] [(layer_pair <layer_id> <layer_id>)])
+// Describes crosstalk between wires running alongside each other, either on
+// the same layer (parallel) or on adjacent layers (tandem).
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnNoise {
+    pub gap: Option<f64>,
+    pub threshold: Option<f64>,
+    pub weight: Option<f64>,
+    pub layer_pair: Option<(DsnLayerId, DsnLayerId)>,
+}
+
+// <segment_descriptor> = ([parallel_segment | tandem_segment]
+//    [(gap <positive_dimension>)] [(limit <positive_dimension>)]
+//    [(layer_pair <layer_id> <layer_id>)])
+// Limits the length of wire which may run alongside another wire within |gap|.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnSegment {
+    pub gap: Option<f64>,
+    pub limit: Option<f64>,
+    pub layer_pair: Option<(DsnLayerId, DsnLayerId)>,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnReorder {
+    Daisy,
+    Stagger,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnShieldLoop {
+    Open,
+    Closed,
+}
+
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnStackViaDepth {
+    pub depth: i32,
+    pub layer_pair: Option<(DsnLayerId, DsnLayerId)>,
+}
+
+// <testpoint_rule_descriptor> = (testpoint_rule
+//    [(side [front | back | both])]
+//    [(center_center <positive_dimension>)]
+//    [(comp_edge_center <positive_dimension>)]
+//    [(pin_allow [on | off])])
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnTestpointRule {
+    pub side: Option<DsnSide>,
+    pub center_center: Option<f64>, // Minimum distance between testpoints.
+    pub comp_edge_center: Option<f64>, // Minimum distance from a component edge.
+    pub pin_allow: Option<bool>,    // Whether pins may be used as testpoints.
+}
+
+// <via_at_smd_descriptor> = (via_at_smd [on | off]
+//    [(grid [on | off])] [(fit [on | off])])
+// Whether vias may be placed under SMD pads.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnViaAtSmd {
+    pub allowed: bool,
+    pub grid: Option<bool>, // Whether such vias must be on the via grid.
+    pub fit: Option<bool>,  // Whether such vias must fit within the pad.
 }
 
 // <class_descriptor> = (class <class_id>
@@ -414,9 +1116,134 @@ pub enum DsnRule {
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnClass {
     pub class_id: DsnId,
-    pub net_ids: Vec<DsnId>,
+    pub net_ids: Vec<DsnId>, // Includes nets expanded from |composites|.
+    pub composites: Vec<DsnCompositeNameList>,
+    pub circuits: Vec<DsnCircuit>,
+    pub rules: Vec<DsnRule>,
+    pub layer_rules: Vec<DsnLayerRule>,
+    pub topology: Option<DsnTopology>,
+}
+
+// <composite_name_list> = (composite [(prefix <prefix>)] <begin_index>
+//    <end_index> [<step>] [(suffix <suffix>)])
+// Describes a numbered series of names, e.g. DATA0 to DATA31.
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DsnCompositeNameList {
+    pub prefix: String,
+    pub begin_index: i32,
+    pub end_index: i32,
+    pub step: i32,
+    pub suffix: String,
+}
+
+impl Default for DsnCompositeNameList {
+    fn default() -> Self {
+        Self { prefix: String::new(), begin_index: 0, end_index: 0, step: 1, suffix: String::new() }
+    }
+}
+
+impl DsnCompositeNameList {
+    // Returns the number of names in the list, checking that |step| is
+    // positive and the list is no longer than |MAX_EXPANSION|.
+    pub fn count(&self) -> Result<i64> {
+        if self.step <= 0 {
+            return Err(eyre!("composite step must be positive, got {}", self.step));
+        }
+        // Computed in i64 so that no i32 range can overflow.
+        let span = (i64::from(self.end_index) - i64::from(self.begin_index)).abs();
+        let count = span / i64::from(self.step) + 1;
+        if count > i64::from(MAX_EXPANSION) {
+            return Err(eyre!("composite expands to too many names ({})", count));
+        }
+        Ok(count)
+    }
+
+    // Expands the list into names, counting from |begin_index| towards
+    // |end_index| inclusive.
+    pub fn names(&self) -> Result<Vec<DsnId>> {
+        let count = self.count()?;
+        let step = i64::from(self.step);
+        let dir = if self.end_index < self.begin_index { -step } else { step };
+        let begin = i64::from(self.begin_index);
+        Ok((0..count)
+            .map(|i| format!("{}{}{}", self.prefix, begin + i * dir, self.suffix))
+            .collect())
+    }
+}
+
+// <topology_descriptor> = (topology {[<fromto_descriptor> | <composite_name_list>]})
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnTopology {
+    pub fromtos: Vec<DsnFromTo>,
+    pub composites: Vec<DsnCompositeNameList>,
+}
+
+// <class_class_descriptor> = (class_class (classes {<class_id>})
+//    {[<rule_descriptor> | <layer_rule_descriptor>]})
+// Describes rules between nets of different classes.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnClassClass {
+    pub class_ids: Vec<DsnId>,
+    pub rules: Vec<DsnRule>,
+    pub layer_rules: Vec<DsnLayerRule>,
+}
+
+// <group_descriptor> = (group <group_id> {<fromto_descriptor>}
+//    [<circuit_descriptor>] [<rule_descriptor>] [{<layer_rule_descriptor>}])
+// Describes a set of connections which share rules.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnGroup {
+    pub group_id: DsnId,
+    pub fromtos: Vec<DsnFromTo>,
     pub circuits: Vec<DsnCircuit>,
     pub rules: Vec<DsnRule>,
+    pub layer_rules: Vec<DsnLayerRule>,
+}
+
+// <group_set_descriptor> = (group_set <group_set_id> {<group_id>}
+//    [<circuit_descriptor>] [<rule_descriptor>] [{<layer_rule_descriptor>}])
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnGroupSet {
+    pub group_set_id: DsnId,
+    pub group_ids: Vec<DsnId>,
+    pub circuits: Vec<DsnCircuit>,
+    pub rules: Vec<DsnRule>,
+    pub layer_rules: Vec<DsnLayerRule>,
+}
+
+// <pair_descriptor> = (pair (nets <net_id> <net_id>)
+//    [<rule_descriptor>] [{<layer_rule_descriptor>}])
+// Describes a differential pair.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnPair {
+    pub net_ids: (DsnId, DsnId),
+    pub rules: Vec<DsnRule>,
+    pub layer_rules: Vec<DsnLayerRule>,
+}
+
+// (gap <bundle_gap> [(layer {<layer_id>})])
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnBundleGap {
+    pub amount: f64,
+    pub layer_ids: Vec<DsnLayerId>, // Empty if the gap applies to all layers.
+}
+
+// <bundle_descriptor> = (bundle <bundle_id> (nets {<net_id>})
+//    [{(gap <bundle_gap> [(layer {<layer_id>})])}])
+// Describes a set of nets which should be routed together.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnBundle {
+    pub bundle_id: DsnId,
+    pub net_ids: Vec<DsnId>,
+    pub gaps: Vec<DsnBundleGap>,
 }
 
 // <network_descriptor> = (network
@@ -432,6 +1259,11 @@ pub struct DsnClass {
 pub struct DsnNetwork {
     pub nets: Vec<DsnNet>,
     pub classes: Vec<DsnClass>,
+    pub class_classes: Vec<DsnClassClass>,
+    pub groups: Vec<DsnGroup>,
+    pub group_sets: Vec<DsnGroupSet>,
+    pub pairs: Vec<DsnPair>,
+    pub bundles: Vec<DsnBundle>,
 }
 
 // <library_descriptor> = (library
@@ -448,7 +1280,60 @@ pub struct DsnNetwork {
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnLibrary {
     pub images: Vec<DsnImage>,
+    pub jumpers: Vec<DsnJumper>,
     pub padstacks: Vec<DsnPadstack>,
+    pub via_array_templates: Vec<DsnViaArrayTemplate>,
+    pub directory: Option<String>,
+    pub extra_image_directory: Option<String>,
+    pub family_families: Vec<DsnFamilyFamily>,
+    pub image_images: Vec<DsnImageImage>,
+}
+
+// <jumper_descriptor> = (jumper (length <positive_dimension>)
+//    [(use_via <padstack_id>)] [(height <positive_dimension>)])
+// Describes a wire jumper which may be used to route on a jumper layer.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnJumper {
+    pub length: f64,
+    pub use_via: DsnId, // Padstack used at each end. Empty if unspecified.
+    pub height: Option<f64>,
+}
+
+// <via_array_template_descriptor> = (via_array_template <via_array_template_id>
+//    <rows> <columns> <row_spacing> <column_spacing> [(use_via <padstack_id>)])
+// Describes a grid of vias that can be placed as a unit, e.g. for fanouts.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnViaArrayTemplate {
+    pub template_id: DsnId,
+    pub rows: i32,
+    pub columns: i32,
+    pub row_spacing: f64,
+    pub column_spacing: f64,
+    pub use_via: DsnId, // Empty if unspecified.
+}
+
+// <family_family_descriptor> = (family_family (family <family_id>)
+//    (family <family_id>) {[<rule_descriptor> | <place_rule_descriptor>]})
+// Describes rules between components of two families.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnFamilyFamily {
+    pub family_ids: (DsnId, DsnId),
+    pub rules: Vec<DsnRule>,
+    pub place_rules: Vec<DsnPlaceRule>,
+}
+
+// <image_image_descriptor> = (image_image (image <image_id>)
+//    (image <image_id>) {[<rule_descriptor> | <place_rule_descriptor>]})
+// Describes rules between components using two images.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnImageImage {
+    pub image_ids: (DsnId, DsnId),
+    pub rules: Vec<DsnRule>,
+    pub place_rules: Vec<DsnPlaceRule>,
 }
 
 // <layer_type> = [signal | power | mixed | jumper]
@@ -471,11 +1356,59 @@ pub enum DsnLayerType {
 //    [<rule_descriptor>]
 //    [(cost <cost_descriptor> [(type [length | way])])]
 //    [(use_net {<net_id>})])
+// <cost_descriptor> = [forbidden | high | medium | low | free | <positive_integer> | -1]
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DsnCost {
+    Forbidden,
+    High,
+    Medium,
+    Low,
+    Free,
+    Value(i32), // -1 means forbidden.
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnCostType {
+    Length,
+    Way,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DsnLayerCost {
+    pub cost: DsnCost,
+    pub cost_type: Option<DsnCostType>,
+}
+
+// <direction_type> = [horizontal | vertical | orthogonal | positive_diagonal |
+//    negative_diagonal | diagonal | off]
+// Preferred routing direction on a layer.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnDirection {
+    Horizontal,
+    Vertical,
+    Orthogonal,
+    PositiveDiagonal,
+    NegativeDiagonal,
+    Diagonal,
+    Off,
+}
+
 #[must_use]
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnLayer {
     pub layer_name: DsnId,
     pub layer_type: DsnLayerType,
+    pub properties: Vec<DsnProperty>, // KiCad stores the layer index here.
+    pub direction: Option<DsnDirection>,
+    pub rules: Vec<DsnRule>,
+    pub cost: Option<DsnLayerCost>,
+    pub use_net: Vec<DsnId>, // Nets which may use this layer, e.g. for power layers.
 }
 
 // <plane_descriptor> = (plane <net_id>
@@ -489,6 +1422,73 @@ pub struct DsnPlane {
     pub windows: Vec<DsnWindow>,
 }
 
+// <control_descriptor> = (control [<off_grid_descriptor>]
+//    [<route_to_fanout_only_descriptor>] [<force_to_terminal_point_descriptor>]
+//    [<same_net_checking_descriptor>] [<checking_trim_by_pin_descriptor>]
+//    [<via_at_smd_descriptor>] ...)
+// Router control flags. Each flag is (<name> [on | off]). Controls not
+// modelled here are kept as raw text in |unknown|.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnControl {
+    pub off_grid: Option<bool>,
+    pub route_to_fanout_only: Option<bool>,
+    pub force_to_terminal_point: Option<bool>,
+    pub same_net_checking: Option<bool>,
+    pub checking_trim_by_pin: Option<bool>,
+    pub via_at_smd: Option<DsnViaAtSmd>,
+    pub unknown: Vec<String>,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnGridType {
+    Via,
+    Wire,
+    ViaKeepout,
+    Place,
+    Snap,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnGridDirection {
+    X,
+    Y,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnGridImageType {
+    Smd,
+    Pin,
+}
+
+// <grid_descriptor> = (grid <grid_type> <numeric>
+//    [(direction [x | y])] [(offset <positive_dimension>)]
+//    [(image_type [smd | pin])])
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DsnGrid {
+    pub grid_type: DsnGridType,
+    pub amount: f64,
+    pub direction: Option<DsnGridDirection>,
+    pub offset: Option<f64>,
+    pub image_type: Option<DsnGridImageType>,
+}
+
+// <layer_noise_weight_descriptor> = (layer_noise_weight {<layer_pair_descriptor>})
+// <layer_pair_descriptor> = (layer_pair <layer_id> <layer_id> <layer_weight>)
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnLayerNoiseWeight {
+    pub layer_pair: (DsnLayerId, DsnLayerId),
+    pub weight: f64,
+}
+
 // <boundary_descriptor> = (boundary
 //    [{<path_descriptor>} | <rectangle_descriptor>] [<rule_descriptor>])
 
@@ -510,21 +1510,198 @@ pub struct DsnPlane {
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnStructure {
     pub boundaries: Vec<DsnShape>,
+    pub control: Option<DsnControl>,
+    pub grids: Vec<DsnGrid>,
     pub keepouts: Vec<DsnKeepout>,
+    pub layer_noise_weights: Vec<DsnLayerNoiseWeight>,
     pub layers: Vec<DsnLayer>,
+    pub place_boundary: Vec<DsnShape>,
+    pub place_rules: Vec<DsnPlaceRule>,
     pub planes: Vec<DsnPlane>,
+    pub regions: Vec<DsnRegion>,
     pub rules: Vec<DsnRule>,
     pub vias: Vec<DsnId>,
 }
 
+// <flip_style_descriptor> = (flip_style [mirror_first | rotate_first])
+// Whether a component flipped to the back side is mirrored before or after
+// being rotated.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnFlipStyle {
+    MirrorFirst,
+    RotateFirst,
+}
+
+// <physical_part_mapping_descriptor> = (physical_part_mapping <image_id>
+//    (component {<component_id>}))
+// <logical_part_mapping_descriptor> = (logical_part_mapping <logical_part_id>
+//    (component {<component_id>}))
+// Assigns components to an image or logical part.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnPartMapping {
+    pub part_id: DsnId,
+    pub component_ids: Vec<DsnId>,
+}
+
+// (subgate <subgate_id> <subgate_swap_code> <subgate_pin_id> <subgate_pin_swap_code>)
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnSubgatePin {
+    pub subgate_id: DsnId,
+    pub subgate_swap_code: i32,
+    pub subgate_pin_id: DsnId,
+    pub subgate_pin_swap_code: i32,
+}
+
+// (pin {<pin_id> <gate_id> <gate_swap_code> <gate_pin_id> <gate_pin_swap_code>
+//    [(subgate ...)]})
+// Assigns a pin of a logical part to a gate. A swap code of zero means the
+// gate or pin may not be swapped. Otherwise, gates with equal swap codes may be
+// swapped with each other, as may pins within a gate with equal swap codes.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnLogicalPin {
+    pub pin_id: DsnId,
+    pub gate_id: DsnId,
+    pub gate_swap_code: i32,
+    pub gate_pin_id: DsnId,
+    pub gate_pin_swap_code: i32,
+    pub subgate: Option<DsnSubgatePin>,
+}
+
+// A gate or subgate of a logical part, derived from its pins.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnGate {
+    pub gate_id: DsnId,
+    pub swap_code: i32,
+    pub pin_ids: Vec<DsnId>,
+}
+
+// <logical_part_descriptor> = (logical_part <logical_part_id>
+//    {<pin_descriptor>} [<rule_descriptor>])
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnLogicalPart {
+    pub logical_part_id: DsnId,
+    pub pins: Vec<DsnLogicalPin>,
+    pub rules: Vec<DsnRule>,
+}
+
+impl DsnLogicalPart {
+    // Gates in order of first appearance.
+    #[must_use]
+    pub fn gates(&self) -> Vec<DsnGate> {
+        let mut gates: Vec<DsnGate> = Vec::new();
+        for pin in &self.pins {
+            add_gate_pin(&mut gates, &pin.gate_id, pin.gate_swap_code, &pin.pin_id);
+        }
+        gates
+    }
+
+    // Subgates in order of first appearance.
+    #[must_use]
+    pub fn subgates(&self) -> Vec<DsnGate> {
+        let mut gates: Vec<DsnGate> = Vec::new();
+        for pin in &self.pins {
+            if let Some(s) = &pin.subgate {
+                add_gate_pin(&mut gates, &s.subgate_id, s.subgate_swap_code, &pin.pin_id);
+            }
+        }
+        gates
+    }
+
+    // Groups of pins which may be swapped with each other. Each group shares a
+    // gate and a non-zero gate pin swap code.
+    #[must_use]
+    pub fn pin_equivalences(&self) -> Vec<Vec<DsnId>> {
+        let mut keys: Vec<(&DsnId, i32)> = Vec::new();
+        let mut groups: Vec<Vec<DsnId>> = Vec::new();
+        for pin in self.pins.iter().filter(|p| p.gate_pin_swap_code != 0) {
+            let key = (&pin.gate_id, pin.gate_pin_swap_code);
+            if let Some(idx) = keys.iter().position(|k| *k == key) {
+                groups[idx].push(pin.pin_id.clone());
+            } else {
+                keys.push(key);
+                groups.push(vec![pin.pin_id.clone()]);
+            }
+        }
+        groups.retain(|g| g.len() > 1);
+        groups
+    }
+}
+
+fn add_gate_pin(gates: &mut Vec<DsnGate>, gate_id: &DsnId, swap_code: i32, pin_id: &DsnId) {
+    if let Some(g) = gates.iter_mut().find(|g| &g.gate_id == gate_id) {
+        g.pin_ids.push(pin_id.clone());
+    } else {
+        gates.push(DsnGate { gate_id: gate_id.clone(), swap_code, pin_ids: vec![pin_id.clone()] });
+    }
+}
+
+// <part_library_descriptor> = (part_library
+//    [{<physical_part_mapping_descriptor>}]
+//    [{<logical_part_mapping_descriptor>}]
+//    [{<logical_part_descriptor>}])
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnPartLibrary {
+    pub physical_part_mappings: Vec<DsnPartMapping>,
+    pub logical_part_mappings: Vec<DsnPartMapping>,
+    pub logical_parts: Vec<DsnLogicalPart>,
+}
+
+// <cluster_descriptor> = (cluster <cluster_id> (comp {<component_id>})
+//    [<place_rule_descriptor>])
+// A set of components which should be placed near each other.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnCluster {
+    pub cluster_id: DsnId,
+    pub component_ids: Vec<DsnId>,
+    pub place_rules: Vec<DsnPlaceRule>,
+}
+
+// <room_descriptor> = (room <room_id> {[<rectangle_descriptor> | <polygon_descriptor>]}
+//    [(side [front | back | both])]
+//    [(include {[<component_id> | <cluster_id>]})]
+//    [(exclude {[<component_id> | <cluster_id>]})]
+//    [<place_rule_descriptor>])
+// An area of the board which components or clusters are assigned to.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnRoom {
+    pub room_id: DsnId,
+    pub shapes: Vec<DsnShape>,
+    pub side: Option<DsnSide>,
+    pub include: Vec<DsnId>, // Components or clusters which must be placed in the room.
+    pub exclude: Vec<DsnId>, // Components or clusters which must not be placed in the room.
+    pub place_rules: Vec<DsnPlaceRule>,
+}
+
+// <floor_plan_descriptor> = (floor_plan [<resolution_descriptor>]
+//    {<cluster_descriptor>} {<room_descriptor>})
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnFloorPlan {
+    pub resolution: Option<DsnResolution>,
+    pub clusters: Vec<DsnCluster>,
+    pub rooms: Vec<DsnRoom>,
+}
+
 // <placement_descriptor> = (placement
 //    [<unit_descriptor> | <resolution_descriptor> | null]
 //    [<place_control_descriptor>]
 //    {<component_instance>})
+// <place_control_descriptor> = (place_control [<flip_style_descriptor>])
 // Describes the location of components on the pcb.
 #[must_use]
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnPlacement {
+    pub flip_style: Option<DsnFlipStyle>,
     pub components: Vec<DsnComponent>,
 }
 
@@ -545,6 +1722,64 @@ impl Default for DsnResolution {
     }
 }
 
+// Units for the electrical resolution descriptors. Each descriptor uses a
+// fixed unit.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnElectricalUnit {
+    Farad,
+    Mho,
+    Amp,
+    Mhenry,
+    Kohm,
+    Sec,
+    Volt,
+}
+
+// <capacitance_resolution_descriptor> = (capacitance_resolution farad <positive_integer>)
+// <conductance_resolution_descriptor> = (conductance_resolution mho <positive_integer>)
+// <current_resolution_descriptor> = (current_resolution amp <positive_integer>)
+// <inductance_resolution_descriptor> = (inductance_resolution mhenry <positive_integer>)
+// <resistance_resolution_descriptor> = (resistance_resolution kohm <positive_integer>)
+// <time_resolution_descriptor> = (time_resolution sec <positive_integer>)
+// <voltage_resolution_descriptor> = (voltage_resolution volt <positive_integer>)
+// Like |DsnResolution|, there are |amount| divisions in |unit|.
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DsnElectricalResolution {
+    pub unit: DsnElectricalUnit,
+    pub amount: i32,
+}
+
+// (color <color_number> [<color_name>] <red> <green> <blue>)
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnColor {
+    pub color_id: i32,
+    pub name: String, // Empty if unnamed.
+    pub rgb: (u8, u8, u8),
+}
+
+// (set_color <object_id> <color_number>)
+// Assigns a color from the table to a kind of object, e.g. a layer.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnSetColor {
+    pub object_id: DsnId,
+    pub color_id: i32,
+}
+
+// <color_descriptor> = (colors {<color>} {<set_color>} {<set_pattern>})
+// Display colors. Pattern settings are kept as raw text in |unknown|.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnColors {
+    pub colors: Vec<DsnColor>,
+    pub set_colors: Vec<DsnSetColor>,
+    pub unknown: Vec<String>,
+}
+
 #[must_use]
 #[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
 #[strum(serialize_all = "snake_case")]
@@ -555,6 +1790,8 @@ pub enum DsnWireType {
     Protect,
 }
 
+// Parsed from literals rather than lexed as keywords, since e.g. "test" is a
+// common identifier.
 #[must_use]
 #[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
 #[strum(serialize_all = "snake_case")]
@@ -565,6 +1802,15 @@ pub enum DsnWireAttr {
     Jumper,
 }
 
+// (terminal <object_type> [<pin_reference>])
+// Describes one end of a wire connection.
+#[must_use]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DsnTerminal {
+    pub object_type: DsnObjectType,
+    pub pin_ref: Option<DsnPinRef>,
+}
+
 // <wire_shape_descriptor> = (wire
 //    <shape_descriptor>
 //    [(net <net_id>)]
@@ -580,7 +1826,27 @@ pub enum DsnWireAttr {
 // Describes a trace. Traces may have any shape.
 #[must_use]
 #[derive(Debug, Default, Clone, PartialEq)]
-pub struct DsnWire {}
+pub struct DsnWire {
+    pub shape: DsnShape, // One of path, qarc, polygon or rect.
+    pub net_id: DsnId,   // Empty if the wire is not attached to a net.
+    pub turret: Option<i32>,
+    pub wire_type: Option<DsnWireType>,
+    pub attr: Option<DsnWireAttr>,
+    pub shield_net_id: DsnId, // Net this wire shields, if it is a shield wire.
+    pub windows: Vec<DsnWindow>,
+    pub connect: Option<(DsnTerminal, DsnTerminal)>,
+    pub supply: bool,
+}
+
+#[must_use]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumDisplay)]
+#[strum(serialize_all = "snake_case")]
+pub enum DsnViaAttr {
+    Test,
+    Fanout,
+    Jumper,
+    VirtualPin(DsnId), // Name of the virtual pin at this via.
+}
 
 // <wire_via_descriptor> = (via
 //    <padstack_id> {<vertex>}
@@ -596,7 +1862,47 @@ pub struct DsnWire {}
 // Describes a via.
 #[must_use]
 #[derive(Debug, Default, Clone, PartialEq)]
-pub struct DsnVia {}
+pub struct DsnVia {
+    pub padstack_id: DsnId,
+    pub pts: Vec<Pt>,  // A via is placed at each of these locations.
+    pub net_id: DsnId, // Empty if the via is not attached to a net.
+    pub via_number: Option<i32>,
+    pub via_type: Option<DsnWireType>,
+    pub attr: Option<DsnViaAttr>,
+    pub contact_layers: Vec<DsnLayerId>, // Layers the via connects to, if restricted.
+    pub supply: bool,
+}
+
+// Describes a virtual pin, a named point that wires can be routed through.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnVirtualPin {
+    pub name: DsnId,
+    pub p: Pt,
+    pub net_id: DsnId,
+}
+
+// <test_points_descriptor> = (test_points {<test_point_descriptor>})
+// <test_point_descriptor> = (point <vertex> [front | back] [<layer_id>]
+//    [(net <net_id>)])
+// Describes a location where a test probe contacts the board.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnTestPoint {
+    pub p: Pt,
+    pub side: Option<DsnSide>,
+    pub layer_id: DsnLayerId, // Empty if unspecified.
+    pub net_id: DsnId,        // Empty if unspecified.
+}
+
+// <supply_pin_descriptor> = (supply_pin {<pin_reference>} [(net <net_id>)])
+// Pins which supply power to a net, e.g. for power fanout.
+#[must_use]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DsnSupplyPin {
+    pub pins: Vec<DsnPinRef>,
+    pub net_id: DsnId,
+}
 
 // <wiring_descriptor> = (wiring
 //    [<unit_descriptor> | <resolution_descriptor> | null]
@@ -609,6 +1915,9 @@ pub struct DsnVia {}
 pub struct DsnWiring {
     pub wires: Vec<DsnWire>,
     pub vias: Vec<DsnVia>,
+    pub virtual_pins: Vec<DsnVirtualPin>,
+    pub test_points: Vec<DsnTestPoint>,
+    pub supply_pins: Vec<DsnSupplyPin>,
 }
 
 // <design_descriptor> = (pcb <pcb_id>
@@ -630,16 +1939,40 @@ pub struct DsnWiring {
 //    [<network_descriptor> | <file_descriptor>]
 //    [<wiring_descriptor>]
 //    [<color_descriptor>])
+// <file_descriptor> = (file <file_path_name>)
 // Describes an overall PCB.
 #[must_use]
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct DsnPcb {
     pub pcb_id: DsnId,
+    pub capacitance_resolution: Option<DsnElectricalResolution>,
+    pub colors: Option<DsnColors>,
+    pub conductance_resolution: Option<DsnElectricalResolution>,
+    pub current_resolution: Option<DsnElectricalResolution>,
+    pub files: Vec<String>, // Unresolved <file_descriptor> paths, in order.
+    pub floor_plan: DsnFloorPlan,
+    pub inductance_resolution: Option<DsnElectricalResolution>,
     pub library: DsnLibrary,
     pub network: DsnNetwork,
+    // Exact form of each number parsed into an f64, keyed by f64::to_bits. If
+    // several forms give the same f64, the first one is kept.
+    pub numbers: HashMap<u64, DsnNumber>,
+    pub part_library: DsnPartLibrary,
     pub placement: DsnPlacement,
+    pub resistance_resolution: Option<DsnElectricalResolution>,
     pub resolution: DsnResolution,
     pub structure: DsnStructure,
+    pub time_resolution: Option<DsnElectricalResolution>,
     pub unit: DsnResolution, // Overrides |resolution| - amount is always 1.
+    pub voltage_resolution: Option<DsnElectricalResolution>,
     pub wiring: DsnWiring,
 }
+
+impl DsnPcb {
+    // Returns the exact form of |v| as it was written, if it was parsed from
+    // the design.
+    #[must_use]
+    pub fn exact_number(&self, v: f64) -> Option<&DsnNumber> {
+        self.numbers.get(&v.to_bits())
+    }
+}
@@ -1,5 +1,6 @@
 use eyre::Result;
 use memedsn::lexer::Lexer;
+use memedsn::loader::{FileResolver, MemResolver, load_dsn_with};
 use memedsn::parser::Parser;
 use memedsn::types::DsnPcb;
 
@@ -76,3 +77,60 @@ fn parser_directive_space_in_quoted_tokens_off_without_quotes() -> Result<()> {
     assert_eq!(pcb.placement.components[0].refs[0].component_id, "R1");
     Ok(())
 }
+
+#[test]
+fn load_with_file_descriptors() -> Result<()> {
+    let mut resolver = MemResolver::default();
+    resolver.insert(
+        "boards/main.dsn",
+        r"
+        (pcb main
+            (file shared/lib.dsn)
+            (file net.dsn)
+            (structure (layer Top (type signal)))
+        )
+    ",
+    );
+    resolver
+        .insert("boards/shared/lib.dsn", "(library (padstack pad1) (image img1 (pin pad1 1 0 0)))");
+    resolver.insert("boards/net.dsn", "(network (net GND (pins U1-1)))");
+
+    let pcb = load_dsn_with("boards/main.dsn", &resolver)?;
+    assert!(pcb.files.is_empty());
+    assert_eq!(pcb.library.padstacks[0].padstack_id, "pad1");
+    assert_eq!(pcb.library.images[0].image_id, "img1");
+    assert_eq!(pcb.network.nets[0].net_id, "GND");
+    assert_eq!(pcb.structure.layers.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn load_with_quoted_file_contents() -> Result<()> {
+    let mut resolver = MemResolver::default();
+    resolver.insert(
+        "main.dsn",
+        r#"(pcb main (parser (string_quote ")) (file lib.dsn) (network (net "Net 1")))"#,
+    );
+    resolver.insert("lib.dsn", r#"(library (image "R 0805"))"#);
+
+    let resolver: &dyn FileResolver = &resolver;
+    let pcb = load_dsn_with("main.dsn", resolver)?;
+    assert_eq!(pcb.network.nets[0].net_id, "Net 1");
+    assert_eq!(pcb.library.images[0].image_id, "R 0805");
+    Ok(())
+}
+
+#[test]
+fn load_with_section_inline_and_in_file() {
+    let mut resolver = MemResolver::default();
+    resolver.insert("main.dsn", "(pcb main (file lib.dsn) (library (padstack pad1)))");
+    resolver.insert("lib.dsn", "(library (image img1))");
+    assert!(load_dsn_with("main.dsn", &resolver).is_err());
+}
+
+#[test]
+fn load_with_missing_file() {
+    let mut resolver = MemResolver::default();
+    resolver.insert("main.dsn", "(pcb main (file lib.dsn))");
+    assert!(load_dsn_with("main.dsn", &resolver).is_err());
+}